@@ -3,10 +3,19 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use my_first_contract::msg::{AllGardenersResponse, HandleMsg, InitMsg, QueryMsg};
+use my_first_contract::msg::{
+    AllGardenersResponse, BonsaiHistoryResponse, ConfigResponse, CurrentPriceResponse, HandleMsg,
+    HasRoleResponse, InitMsg, PauseStatusResponse, PresaleStatusResponse, QueryMsg, ReceiveMsg,
+    RoyaltyInfoResponse, SaleEventsResponse, SearchBonsaisResponse, SupplyResponse,
+    TopGardenersResponse,
+};
 use my_first_contract::state::Bonsai;
 use my_first_contract::state::BonsaiList;
 use my_first_contract::state::Gardener;
+use my_first_contract::state::Lineage;
+use my_first_contract::state::RandomPurchase;
+use my_first_contract::state::Stats;
+use my_first_contract::state::Tombstone;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -15,10 +24,26 @@ fn main() {
     remove_schemas(&out_dir).unwrap();
 
     export_schema(&schema_for!(InitMsg), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
     export_schema(&schema_for!(Gardener), &out_dir);
     export_schema(&schema_for!(Bonsai), &out_dir);
     export_schema(&schema_for!(BonsaiList), &out_dir);
     export_schema(&schema_for!(AllGardenersResponse), &out_dir);
+    export_schema(&schema_for!(RoyaltyInfoResponse), &out_dir);
+    export_schema(&schema_for!(CurrentPriceResponse), &out_dir);
+    export_schema(&schema_for!(SaleEventsResponse), &out_dir);
+    export_schema(&schema_for!(PresaleStatusResponse), &out_dir);
+    export_schema(&schema_for!(PauseStatusResponse), &out_dir);
+    export_schema(&schema_for!(HasRoleResponse), &out_dir);
+    export_schema(&schema_for!(RandomPurchase), &out_dir);
+    export_schema(&schema_for!(Lineage), &out_dir);
+    export_schema(&schema_for!(SupplyResponse), &out_dir);
+    export_schema(&schema_for!(Tombstone), &out_dir);
+    export_schema(&schema_for!(BonsaiHistoryResponse), &out_dir);
+    export_schema(&schema_for!(Stats), &out_dir);
+    export_schema(&schema_for!(TopGardenersResponse), &out_dir);
+    export_schema(&schema_for!(SearchBonsaisResponse), &out_dir);
 }
@@ -105,10 +105,19 @@ const connect = async (
   return { client, address }
 }
 
+interface Cw20Coin {
+  readonly address: string,
+  readonly amount: string,
+}
+
+type Price = { readonly native: Coin } | { readonly cw20: Cw20Coin };
+
 interface Bonsai {
   readonly id: string,
   readonly birth_date: number,
-  readonly price: Coin;
+  readonly prices: Price[];
+  // not revealed yet when the shop uses commit reveal
+  readonly traits?: Traits,
 }
 
 interface BonsaiList {
@@ -119,30 +128,265 @@ interface Gardener {
   readonly name: string,
   readonly address: string,
   readonly bonsais: Bonsai[];
+  readonly purchased: number,
 }
 
 interface AllGardenersResponse {
   readonly gardeners: Gardener[];
 }
 
+type PricingMode =
+  | { readonly fixed: {} }
+  | { readonly linear_curve: { step_bps: number } }
+  | { readonly exponential_curve: { step_bps: number } }
+  | { readonly dutch_auction: { decay_bps: number, floor_bps: number } };
+
+type Role = "minter" | "price_manager" | "pauser";
+
+type Species = "juniper" | "pine" | "maple" | "ficus" | "elm" | "azalea";
+
+type Rarity = "common" | "uncommon" | "rare" | "legendary";
+
+interface Traits {
+  readonly species: Species,
+  readonly rarity: Rarity,
+}
+
+interface HasRoleResponse {
+  readonly has_role: boolean,
+}
+
+type PauseScope = "purchases" | "transfers" | "all";
+
+interface Lineage {
+  readonly parents: number[],
+  readonly generation: number,
+}
+
+interface SupplyResponse {
+  readonly total_supply: number,
+  readonly minted: number,
+  readonly sold: number,
+  readonly owned: number,
+  readonly burned: number,
+}
+
+interface Tombstone {
+  readonly id: number,
+  readonly last_owner: string,
+  readonly cut_height: number,
+}
+
+type HistoryKind = "buy" | "sell" | "transfer" | "cut";
+
+interface HistoryEntry {
+  readonly index: number,
+  readonly kind: HistoryKind,
+  readonly from?: string,
+  readonly to?: string,
+  readonly price?: Price,
+  readonly height: number,
+}
+
+interface BonsaiHistoryResponse {
+  readonly entries: HistoryEntry[],
+}
+
+interface PriceFilter {
+  readonly asset: string,
+  readonly min?: string,
+  readonly max?: string,
+}
+
+interface BonsaiFilter {
+  readonly price?: PriceFilter,
+  readonly min_birth_date?: number,
+  readonly max_birth_date?: number,
+  readonly owner?: string,
+  readonly for_sale?: boolean,
+  readonly species?: Species,
+  readonly rarity?: Rarity,
+}
+
+type SearchOrder = "oldest" | "newest" | "cheapest" | "priciest";
+
+interface FoundBonsai {
+  readonly bonsai: Bonsai,
+  readonly owner?: string,
+  readonly for_sale: boolean,
+  // the list prices the search filters and sorts by, none when not for sale
+  readonly prices: Price[],
+}
+
+interface SearchBonsaisResponse {
+  readonly bonsais: FoundBonsai[],
+}
+
+type Leaderboard = "bonsai_count" | "oldest_tree" | { readonly total_spent: { readonly asset: string } };
+
+interface GardenerScores {
+  readonly bonsai_count: number,
+  readonly spent: Price[],
+  readonly oldest_tree?: number,
+}
+
+interface RankedGardener {
+  readonly address: string,
+  readonly scores: GardenerScores,
+}
+
+interface TopGardenersResponse {
+  readonly gardeners: RankedGardener[],
+}
+
+interface Stats {
+  readonly sales: number,
+  readonly volume: Price[],
+  readonly floor: Price[],
+  readonly last_sale?: Price,
+  readonly gardeners: number,
+}
+
+interface RandomPurchase {
+  readonly price: Price,
+  readonly height: number,
+}
+
+interface PauseStatusResponse {
+  readonly scope?: PauseScope,
+}
+
+interface Presale {
+  readonly public_sale_height: number,
+  readonly merkle_root?: string,
+}
+
+interface PresaleStatusResponse {
+  readonly presale_active: boolean,
+  readonly public_sale_height?: number,
+  readonly allowlisted: boolean,
+  readonly purchased: number,
+  readonly max_per_wallet?: number,
+}
+
+interface CurrentPriceResponse {
+  readonly prices: Price[];
+  readonly discount_percent: number;
+}
+
+interface SaleEvent {
+  readonly start_height: number,
+  readonly end_height: number,
+  readonly discount_percent: number,
+}
+
+interface SaleEventsResponse {
+  readonly events: SaleEvent[];
+  readonly active_discount_percent: number;
+}
+
 interface InitMsg {
-  readonly price: Coin,
+  readonly owner?: string,
+  readonly prices: Price[],
   readonly number: number,
+  readonly cw20_whitelist: string[],
+  readonly protocol_fee_bps: number,
+  readonly fee_collector: string,
+  readonly royalty_bps: number,
+  readonly pricing: PricingMode,
+  readonly presale?: Presale,
+  readonly max_per_wallet?: number,
+  readonly max_initial_inventory?: number,
+  readonly commit_reveal: boolean,
+}
+
+interface ConfigResponse {
+  readonly owner: string,
+  readonly default_prices: Price[],
+  readonly cw20_whitelist: string[],
+  readonly fee_collector: string,
+  readonly protocol_fee_bps: number,
+  readonly royalty_bps: number,
+  readonly pricing: PricingMode,
+  readonly presale?: Presale,
+  readonly max_per_wallet?: number,
+  readonly max_initial_inventory: number,
+  readonly commit_reveal: boolean,
+}
+
+// only the given fields are changed
+interface ConfigUpdate {
+  readonly owner?: string,
+  readonly default_prices?: Price[],
+  readonly cw20_whitelist?: string[],
+  readonly fee_collector?: string,
+  readonly protocol_fee_bps?: number,
+  readonly pricing?: PricingMode,
+  readonly presale?: Presale,
+  readonly clear_presale?: boolean,
+  readonly max_per_wallet?: number,
+  readonly clear_max_per_wallet?: boolean,
+  readonly max_initial_inventory?: number,
+}
+
+interface RoyaltyInfoResponse {
+  readonly fee_collector: string,
+  readonly protocol_fee: string,
+  readonly royalty_recipient?: string,
+  readonly royalty: string,
+  readonly seller_amount: string,
 }
 
 interface BonsaiInstance {
   readonly contractAddress: string
 
   // queries
+  getConfig: () => Promise<ConfigResponse>
   getBonsais: () => Promise<BonsaiList>
+  getBonsai: (b_id: number) => Promise<Bonsai>
   getGardener: (address?: string) => Promise<Gardener>
   getGardeners: () => Promise<AllGardenersResponse>
+  getRoyaltyInfo: (b_id: number, sale_price: string) => Promise<RoyaltyInfoResponse>
+  getCurrentPrice: (b_id: number) => Promise<CurrentPriceResponse>
+  getSaleEvents: () => Promise<SaleEventsResponse>
+  getPresaleStatus: (address?: string) => Promise<PresaleStatusResponse>
+  getPauseStatus: () => Promise<PauseStatusResponse>
+  hasRole: (address: string, role: Role) => Promise<HasRoleResponse>
+  getRandomPurchase: (address?: string) => Promise<RandomPurchase | null>
+  getLineage: (b_id: number) => Promise<Lineage>
+  getSupply: () => Promise<SupplyResponse>
+  getStats: () => Promise<Stats>
+  topGardeners: (by: Leaderboard, limit?: number) => Promise<TopGardenersResponse>
+  searchBonsais: (filter: BonsaiFilter, order?: SearchOrder, start_after?: number, limit?: number) => Promise<SearchBonsaisResponse>
+  getTombstone: (b_id: number) => Promise<Tombstone | null>
+  getBonsaiHistory: (b_id: number, start_after?: number, limit?: number) => Promise<BonsaiHistoryResponse>
 
   // actions
   becomeGardener: (name: string) => Promise<string>
   buyBonsai: (b_id: number, sent_funds: Coin[]) => Promise<string>
-  sellBonsai: (recipient: string, b_id: number) => Promise<string>
-  cutBonsai: (b_id: number) => Promise<string>
+  buyBonsaiWithCw20: (token: string, b_id: number, amount: string) => Promise<string>
+  sellBonsai: (recipient: string, b_id: number, prices: Price[]) => Promise<string>
+  cancelSale: (b_id: number) => Promise<string>
+  cutBonsai: (b_id: number, propagate?: boolean) => Promise<string>
+  buyBonsais: (b_ids: number[], sent_funds: Coin[]) => Promise<string>
+  buyRandomBonsai: (sent_funds: Coin[]) => Promise<string>
+  crossBonsais: (parent_a: number, parent_b: number) => Promise<string>
+  revealRandomBonsai: () => Promise<string>
+  sellBonsais: (recipient: string, b_ids: number[], prices: Price[]) => Promise<string>
+  cutBonsais: (b_ids: number[]) => Promise<string>
+  setBonsaiPrice: (b_id: number, prices: Price[]) => Promise<string>
+  setShopPrice: (prices: Price[]) => Promise<string>
+  addSaleEvent: (event: SaleEvent) => Promise<string>
+  updateAllowlist: (add: string[], remove: string[]) => Promise<string>
+  joinPresale: (proof: string[]) => Promise<string>
+  commitSeed: (b_id: number, commitment: string) => Promise<string>
+  revealTraits: (b_id: number, secret?: string) => Promise<string>
+  pause: (scope: PauseScope) => Promise<string>
+  unpause: () => Promise<string>
+  growBonsais: (number: number, prices?: Price[]) => Promise<string>
+  updateConfig: (update: ConfigUpdate) => Promise<string>
+  grantRole: (address: string, role: Role) => Promise<string>
+  revokeRole: (address: string, role: Role) => Promise<string>
 }
 
 interface BonsaiContract {
@@ -160,10 +404,18 @@ interface BonsaiContract {
 
 const bonsaiCW = (client: SigningCosmWasmClient, metaSource: string, builderSource: string, contractSource: string) : BonsaiContract => {
   const use = (contractAddress: string): BonsaiInstance => {
+    const getConfig = async (): Promise<ConfigResponse> => {
+      return await client.queryContractSmart(contractAddress, {get_config: {}});
+    }
+
     const getBonsais = async (): Promise<BonsaiList> => {
       return await client.queryContractSmart(contractAddress, {get_bonsais: {}});
     }
 
+    const getBonsai = async (b_id: number): Promise<Bonsai> => {
+      return await client.queryContractSmart(contractAddress, {get_bonsai: {b_id}});
+    }
+
     const getGardener = async (address?: string): Promise<Gardener> => {
       const sender = address || client.senderAddress;
       return await client.queryContractSmart(contractAddress, {get_gardener: {sender}});
@@ -173,6 +425,69 @@ const bonsaiCW = (client: SigningCosmWasmClient, metaSource: string, builderSour
       return await client.queryContractSmart(contractAddress, {get_gardeners: {}});
     }
 
+    const getRoyaltyInfo = async (b_id: number, sale_price: string) : Promise<RoyaltyInfoResponse> => {
+      return await client.queryContractSmart(contractAddress, {get_royalty_info: {b_id, sale_price}});
+    }
+
+    const getCurrentPrice = async (b_id: number) : Promise<CurrentPriceResponse> => {
+      return await client.queryContractSmart(contractAddress, {get_current_price: {b_id}});
+    }
+
+    const getSaleEvents = async () : Promise<SaleEventsResponse> => {
+      return await client.queryContractSmart(contractAddress, {get_sale_events: {}});
+    }
+
+    const getPresaleStatus = async (address?: string) : Promise<PresaleStatusResponse> => {
+      const sender = address || client.senderAddress;
+      return await client.queryContractSmart(contractAddress, {get_presale_status: {address: sender}});
+    }
+
+    const getPauseStatus = async () : Promise<PauseStatusResponse> => {
+      return await client.queryContractSmart(contractAddress, {get_pause_status: {}});
+    }
+
+    const hasRole = async (address: string, role: Role) : Promise<HasRoleResponse> => {
+      return await client.queryContractSmart(contractAddress, {has_role: {address, role}});
+    }
+
+    // the random bonsai paid and not revealed yet
+    const getRandomPurchase = async (address?: string) : Promise<RandomPurchase | null> => {
+      const sender = address || client.senderAddress;
+      return await client.queryContractSmart(contractAddress, {get_random_purchase: {address: sender}});
+    }
+
+    const getLineage = async (b_id: number) : Promise<Lineage> => {
+      return await client.queryContractSmart(contractAddress, {get_lineage: {b_id}});
+    }
+
+    // volume and floor are given in each asset bonsais were paid with
+    const getStats = async () : Promise<Stats> => {
+      return await client.queryContractSmart(contractAddress, {get_stats: {}});
+    }
+
+    // sorting by price needs a price filter, pass the id of the last bonsai received to get the next page
+    const searchBonsais = async (filter: BonsaiFilter, order?: SearchOrder, start_after?: number, limit?: number) : Promise<SearchBonsaisResponse> => {
+      return await client.queryContractSmart(contractAddress, {search_bonsais: {filter, order, start_after, limit}});
+    }
+
+    const topGardeners = async (by: Leaderboard, limit?: number) : Promise<TopGardenersResponse> => {
+      return await client.queryContractSmart(contractAddress, {top_gardeners: {by, limit}});
+    }
+
+    const getSupply = async () : Promise<SupplyResponse> => {
+      return await client.queryContractSmart(contractAddress, {get_supply: {}});
+    }
+
+    // the tombstone of a cut bonsai
+    const getTombstone = async (b_id: number) : Promise<Tombstone | null> => {
+      return await client.queryContractSmart(contractAddress, {get_tombstone: {b_id}});
+    }
+
+    // oldest entries first, pass the index of the last entry received to get the next page
+    const getBonsaiHistory = async (b_id: number, start_after?: number, limit?: number) : Promise<BonsaiHistoryResponse> => {
+      return await client.queryContractSmart(contractAddress, {get_bonsai_history: {b_id, start_after, limit}});
+    }
+
     const becomeGardener = async (name: string): Promise<string> => {
       const result = await client.execute(contractAddress, {become_gardener:{name}});
       return result.transactionHash;
@@ -183,25 +498,188 @@ const bonsaiCW = (client: SigningCosmWasmClient, metaSource: string, builderSour
       return  result.transactionHash;
     }
 
-    const sellBonsai = async(recipient: string, b_id: number): Promise<string> => {
-      const result = await  client.execute(contractAddress, {sell_bonsai:{b_id, recipient}});
+    // sends the tokens to the shop through the cw20 contract, embedding the purchase
+    const buyBonsaiWithCw20 = async (token: string, b_id: number, amount: string) : Promise<string> => {
+      const msg = toBase64(toUtf8(JSON.stringify({buy_bonsai: {b_id}})));
+      const result = await client.execute(token, {send: {contract: contractAddress, amount, msg}});
+      return result.transactionHash;
+    }
+
+    // offers the bonsai to the recipient, who completes the sale paying one of the prices with buyBonsai
+    const sellBonsai = async(recipient: string, b_id: number, prices: Price[]): Promise<string> => {
+      const result = await  client.execute(contractAddress, {sell_bonsai:{b_id, recipient, prices}});
+      return result.transactionHash;
+    }
+
+    const cancelSale = async(b_id: number): Promise<string> => {
+      const result = await  client.execute(contractAddress, {cancel_sale:{b_id}});
+      return result.transactionHash;
+    }
+
+    // a propagating cut keeps a mature tree and grows a young clone of it
+    const cutBonsai = async(b_id: number, propagate: boolean = false): Promise<string> => {
+      const result = await  client.execute(contractAddress, {cut_bonsai:{b_id, propagate}});
+      return result.transactionHash;
+    }
+
+    // sent_funds must cover the sum of the bonsai prices
+    const buyBonsais = async (b_ids: number[], sent_funds: Coin[]) : Promise<string> => {
+      const result = await client.execute(contractAddress, {buy_bonsais:{b_ids}}, "", sent_funds);
+      return result.transactionHash;
+    }
+
+    // the bonsai is drawn from the shop by the first transaction of a later block
+    const buyRandomBonsai = async (sent_funds: Coin[]) : Promise<string> => {
+      const result = await client.execute(contractAddress, {buy_random_bonsai:{}}, "", sent_funds);
+      return result.transactionHash;
+    }
+
+    // any transaction draws the random bonsais paid in earlier blocks, this one does nothing else
+    const revealRandomBonsai = async () : Promise<string> => {
+      const result = await client.execute(contractAddress, {reveal_random_bonsai:{}});
+      return result.transactionHash;
+    }
+
+    // both parents must be mature, off cooldown and with revealed traits
+    const crossBonsais = async (parent_a: number, parent_b: number) : Promise<string> => {
+      const result = await client.execute(contractAddress, {cross_bonsais:{parent_a, parent_b}});
+      return result.transactionHash;
+    }
+
+    const sellBonsais = async(recipient: string, b_ids: number[], prices: Price[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {sell_bonsais:{b_ids, recipient, prices}});
+      return result.transactionHash;
+    }
+
+    const cutBonsais = async(b_ids: number[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {cut_bonsais:{b_ids}});
+      return result.transactionHash;
+    }
+
+    // owner or price manager only
+    const setBonsaiPrice = async(b_id: number, prices: Price[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {set_bonsai_price:{b_id, prices}});
+      return result.transactionHash;
+    }
+
+    // owner or price manager only
+    const setShopPrice = async(prices: Price[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {set_shop_price:{prices}});
+      return result.transactionHash;
+    }
+
+    // owner or price manager only
+    const addSaleEvent = async(event: SaleEvent): Promise<string> => {
+      const result = await client.execute(contractAddress, {add_sale_event: event});
+      return result.transactionHash;
+    }
+
+    // owner only
+    const updateAllowlist = async(add: string[], remove: string[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {update_allowlist:{add, remove}});
+      return result.transactionHash;
+    }
+
+    // proof made of the hex encoded sibling hashes, from the leaf up to the merkle root
+    const joinPresale = async(proof: string[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {join_presale:{proof}});
+      return result.transactionHash;
+    }
+
+    // the commitment is the hex encoded sha256 of the secret
+    const commitSeed = async(b_id: number, commitment: string): Promise<string> => {
+      const result = await client.execute(contractAddress, {commit_seed:{b_id, commitment}});
+      return result.transactionHash;
+    }
+
+    // without the secret, anyone can reveal once the deadline has passed
+    const revealTraits = async(b_id: number, secret?: string): Promise<string> => {
+      const result = await client.execute(contractAddress, {reveal_traits:{b_id, secret}});
+      return result.transactionHash;
+    }
+
+    // owner or pauser only
+    const pause = async(scope: PauseScope): Promise<string> => {
+      const result = await client.execute(contractAddress, {pause:{scope}});
+      return result.transactionHash;
+    }
+
+    // owner or pauser only
+    const unpause = async(): Promise<string> => {
+      const result = await client.execute(contractAddress, {unpause:{}});
+      return result.transactionHash;
+    }
+
+    // owner or minter only, the default prices are used if none are given
+    const growBonsais = async(number: number, prices?: Price[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {grow_bonsais:{number, prices}});
+      return result.transactionHash;
+    }
+
+    // owner only
+    const updateConfig = async(update: ConfigUpdate): Promise<string> => {
+      const result = await client.execute(contractAddress, {update_config: update});
+      return result.transactionHash;
+    }
+
+    // owner only
+    const grantRole = async(address: string, role: Role): Promise<string> => {
+      const result = await client.execute(contractAddress, {grant_role:{address, role}});
       return result.transactionHash;
     }
 
-    const cutBonsai = async(b_id: number): Promise<string> => {
-      const result = await  client.execute(contractAddress, {cut_bonsai:{b_id}});
+    // owner only
+    const revokeRole = async(address: string, role: Role): Promise<string> => {
+      const result = await client.execute(contractAddress, {revoke_role:{address, role}});
       return result.transactionHash;
     }
 
     return {
       contractAddress,
+      getConfig,
       getBonsais,
+      getBonsai,
       getGardener,
       getGardeners,
+      getRoyaltyInfo,
+      getCurrentPrice,
+      getSaleEvents,
+      getPresaleStatus,
+      getPauseStatus,
+      hasRole,
+      getRandomPurchase,
+      getLineage,
+      getSupply,
+      getStats,
+      topGardeners,
+      searchBonsais,
+      getTombstone,
+      getBonsaiHistory,
       becomeGardener,
       buyBonsai,
+      buyBonsaiWithCw20,
       sellBonsai,
+      cancelSale,
       cutBonsai,
+      buyBonsais,
+      buyRandomBonsai,
+      revealRandomBonsai,
+      crossBonsais,
+      sellBonsais,
+      cutBonsais,
+      setBonsaiPrice,
+      setShopPrice,
+      addSaleEvent,
+      updateAllowlist,
+      joinPresale,
+      commitSeed,
+      revealTraits,
+      pause,
+      unpause,
+      growBonsais,
+      updateConfig,
+      grantRole,
+      revokeRole,
     };
   }
 
@@ -241,7 +719,7 @@ const bonsaiCW = (client: SigningCosmWasmClient, metaSource: string, builderSour
 // hitFaucet(defaultFaucetUrl, resolvedResult.address, defaultOptions.feeToken)
 // const factory = bonsaiCW(resolvedResult.client, metaSourcePath, optimizerPath, sourceUrl)
 // const codeId = await factory.upload();
-// const contract = await factory.instantiate(codeId, {price: {denom: "ucosm", amount: "5"}, number: 5}, "Bonsai")
+// const contract = await factory.instantiate(codeId, {prices: [{native: {denom: "ucosm", amount: "5"}}], number: 5, cw20_whitelist: [], protocol_fee_bps: 0, fee_collector: resolvedResult.address, royalty_bps: 0, pricing: {fixed: {}}, commit_reveal: false}, "Bonsai")
 // contract.contractAddress -> 'cosmos1danus0j9c3fqrcku3g5qfzupa5etxxrjtrrsm0'
 //
 // OR
@@ -1,224 +1,2402 @@
 use cosmwasm_std::{
-    attr, to_binary, Binary, CanonicalAddr, Deps, DepsMut, Env, HandleResponse, HumanAddr,
-    InitResponse, MessageInfo, Order, StdError, StdResult,
+    attr, from_binary, to_binary, Attribute, BankMsg, Binary, CanonicalAddr, CosmosMsg, Deps,
+    DepsMut, Env, HandleResponse, HumanAddr, InitResponse, MessageInfo, Order, QuerierWrapper,
+    QueryRequest, StdError, StdResult, Storage, Uint128, WasmMsg, WasmQuery,
 };
+use std::collections::BTreeSet;
+
+use cw20::{BalanceResponse, Cw20CoinHuman, Cw20HandleMsg, Cw20QueryMsg, Cw20ReceiveMsg};
 
 use crate::errors::MyCustomError;
-use crate::msg::{AllGardenersResponse, HandleMsg, InitMsg, QueryMsg};
+use crate::events::{self, Event};
+#[cfg(any(test, feature = "invariants"))]
+use crate::msg::InvariantsResponse;
+use crate::msg::{
+    AllGardenersResponse, BonsaiFilter, BonsaiHistoryResponse, ConfigResponse,
+    CurrentPriceResponse, FoundBonsai, HandleMsg, HasRoleResponse, InitMsg, PauseStatusResponse,
+    PresaleStatusResponse, QueryMsg, RankedGardener, ReceiveMsg, RoyaltyInfoResponse,
+    SaleEventsResponse, SearchBonsaisResponse, SearchOrder, SupplyResponse, TopGardenersResponse,
+};
+#[cfg(any(test, feature = "invariants"))]
+use crate::state::duplicated_bonsais;
 use crate::state::{
-    bonsai_store, bonsai_store_read, gardeners_store, gardeners_store_read, BonsaiList, Gardener,
+    active_discount, allowlist_store, allowlist_store_read, block_seeds_store,
+    block_seeds_store_read, bonsai_store, bonsai_store_read, burned_store, burned_store_read,
+    config_store, config_store_read, cooldowns_store, cooldowns_store_read, cw20_whitelist_store,
+    cw20_whitelist_store_read, decode_hash, gardeners_store, gardeners_store_read, grown_store,
+    grown_store_read, history_store_read, index_bonsai, inventory_store, inventory_store_read,
+    last_block_store, last_block_store_read, lineage_store, lineage_store_read, mul_div,
+    paused_store, paused_store_read, random_draws_store, random_draws_store_read,
+    random_purchases_store, random_purchases_store_read, ranking_store_read, record_history,
+    reindex_bonsai, roles_store, roles_store_read, sale_events_store, sale_events_store_read,
+    sale_offers_store, sale_offers_store_read, scores_store_read, search_entries_store_read,
+    search_index_store_read, seed_commitments_store, seed_commitments_store_read, sold_store,
+    sold_store_read, stats_store, stats_store_read, tombstones_store, tombstones_store_read,
+    transfer_bonsai, unindex_bonsai, update_scores, virtual_positions_store,
+    virtual_positions_store_read, virtual_slots_store, virtual_slots_store_read, BlockSeed, Bonsai,
+    BonsaiList, BonsaiOwner, Config, Gardener, HistoryKind, Inventory, Leaderboard, Lineage,
+    PauseScope, Presale, Price, PricingMode, Prng, RandomPurchase, Role, Royalty, SaleEvent,
+    SaleOffer, SearchEntry, SearchIndex, SeedCommitment, Stats, Tombstone, Traits, BPS_DENOMINATOR,
+    CROSS_COOLDOWN_BLOCKS, DEFAULT_MAX_INITIAL_INVENTORY, MATURITY_BLOCKS, MAX_INITIAL_INVENTORY,
+    MAX_RANDOM_DRAWS_PER_BLOCK, MAX_STEP_BPS, REVEAL_DEADLINE_BLOCKS,
 };
 
-// version info for migration purposes
-// TODO check how this should be added
-// const CONTRACT_NAME: &str = "crates.io:bonsai-cw-bragaz";
-// const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// version info for migration purposes
+// TODO check how this should be added
+// const CONTRACT_NAME: &str = "crates.io:bonsai-cw-bragaz";
+// const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// init is like the genesis of cosmos SDK
+pub fn init(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InitMsg,
+) -> Result<InitResponse, MyCustomError> {
+    // set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    if msg.number == 0 {
+        return Err(MyCustomError::EmptyInventory {});
+    }
+    let cw20_whitelist = msg
+        .cw20_whitelist
+        .iter()
+        .map(|token| deps.api.canonical_address(token))
+        .collect::<StdResult<Vec<CanonicalAddr>>>()?;
+    cw20_whitelist_store(deps.storage).save(&cw20_whitelist)?;
+
+    // the creator of the shop earns the royalties of the grown bonsais
+    let royalty = if msg.royalty_bps > 0 {
+        Some(Royalty {
+            recipient: deps.api.canonical_address(&info.sender)?,
+            bps: msg.royalty_bps,
+        })
+    } else {
+        None
+    };
+    let owner = msg.owner.as_ref().unwrap_or(&info.sender);
+    let config = Config {
+        owner: deps.api.canonical_address(owner)?,
+        default_prices: msg.prices.clone(),
+        fee_collector: deps.api.canonical_address(&msg.fee_collector)?,
+        protocol_fee_bps: msg.protocol_fee_bps,
+        pricing: msg.pricing,
+        presale: msg.presale,
+        max_per_wallet: msg.max_per_wallet,
+        royalty: royalty.clone(),
+        max_initial_inventory: msg
+            .max_initial_inventory
+            .unwrap_or(DEFAULT_MAX_INITIAL_INVENTORY),
+        commit_reveal: msg.commit_reveal,
+    };
+    check_config(deps.as_ref(), &config)?;
+    config_store(deps.storage).save(&config)?;
+    sold_store(deps.storage).save(&0)?;
+    burned_store(deps.storage).save(&0)?;
+    stats_store(deps.storage).save(&Stats::default())?;
+    sale_events_store(deps.storage).save(&vec![])?;
+
+    // only the first bonsais are grown, the rest of the inventory is grown when bought
+    let number = msg.number.min(config.max_initial_inventory);
+    inventory_store(deps.storage).save(&Inventory {
+        total_supply: msg.number,
+        minted: number,
+        first_virtual_id: number,
+        end_virtual_id: msg.number,
+        virtual_grown: 0,
+        virtual_birth_date: env.block.height,
+    })?;
+
+    let mut bonsai_list = BonsaiList::grow_bonsais(number, env.block.height, msg.prices, royalty);
+    let creator = deps.api.canonical_address(&info.sender)?;
+    draw_traits(&config, &env, &creator, &mut bonsai_list.bonsais);
+    bonsai_store(deps.storage).save(&bonsai_list)?;
+    for bonsai in bonsai_list.bonsais.iter() {
+        index_bonsai(deps.storage, bonsai, None)?;
+    }
+    last_block_store(deps.storage).save(&env.block.height)?;
+    let mut res = InitResponse::default();
+    res.attributes = Event::new(events::INIT, &env)
+        .from(&info.sender)
+        .attributes();
+    res.attributes.push(attr("number", msg.number));
+    Ok(res)
+}
+
+pub fn handle(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: HandleMsg,
+) -> Result<HandleResponse, MyCustomError> {
+    if let Some(scope) = paused_store_read(deps.storage).may_load()? {
+        if is_frozen(&scope, &msg) {
+            return Err(MyCustomError::ContractPaused {});
+        }
+    }
+    let drawn = record_block(deps.branch(), &env)?;
+
+    let mut res = match msg {
+        HandleMsg::BecomeGardener { name } => handle_become_gardener(deps, env, info, name),
+        HandleMsg::BuyBonsai { b_id } => handle_buy_bonsai(deps, env, info, vec![b_id]),
+        HandleMsg::BuyBonsais { b_ids } => handle_buy_bonsai(deps, env, info, b_ids),
+        HandleMsg::CrossBonsais { parent_a, parent_b } => {
+            handle_cross_bonsais(deps, env, info, parent_a, parent_b)
+        }
+        HandleMsg::BuyRandomBonsai {} => handle_buy_random_bonsai(deps, env, info),
+        HandleMsg::RevealRandomBonsai {} => handle_reveal_random_bonsai(deps, env, info),
+        HandleMsg::SellBonsai {
+            recipient,
+            b_id,
+            prices,
+        } => handle_sell_bonsai(deps, env, info, recipient, vec![b_id], prices),
+        HandleMsg::SellBonsais {
+            recipient,
+            b_ids,
+            prices,
+        } => handle_sell_bonsai(deps, env, info, recipient, b_ids, prices),
+        HandleMsg::CancelSale { b_id } => handle_cancel_sale(deps, env, info, b_id),
+        HandleMsg::CutBonsai { b_id, propagate } => {
+            if propagate {
+                handle_propagate_bonsai(deps, env, info, b_id)
+            } else {
+                handle_cut_bonsai(deps, env, info, vec![b_id])
+            }
+        }
+        HandleMsg::CutBonsais { b_ids } => handle_cut_bonsai(deps, env, info, b_ids),
+        HandleMsg::Receive(msg) => handle_receive(deps, env, info, msg),
+        HandleMsg::SetBonsaiPrice { b_id, prices } => {
+            handle_set_bonsai_price(deps, env, info, b_id, prices)
+        }
+        HandleMsg::SetShopPrice { prices } => handle_set_shop_price(deps, env, info, prices),
+        HandleMsg::AddSaleEvent {
+            start_height,
+            end_height,
+            discount_percent,
+        } => handle_add_sale_event(deps, env, info, start_height, end_height, discount_percent),
+        HandleMsg::UpdateAllowlist { add, remove } => {
+            handle_update_allowlist(deps, env, info, add, remove)
+        }
+        HandleMsg::JoinPresale { proof } => handle_join_presale(deps, env, info, proof),
+        HandleMsg::CommitSeed { b_id, commitment } => {
+            handle_commit_seed(deps, env, info, b_id, commitment)
+        }
+        HandleMsg::RevealTraits { b_id, secret } => {
+            handle_reveal_traits(deps, env, info, b_id, secret)
+        }
+        HandleMsg::Pause { scope } => handle_pause(deps, env, info, scope),
+        HandleMsg::Unpause {} => handle_unpause(deps, env, info),
+        HandleMsg::GrowBonsais { number, prices } => {
+            handle_grow_bonsais(deps, env, info, number, prices)
+        }
+        HandleMsg::UpdateConfig {
+            owner,
+            default_prices,
+            cw20_whitelist,
+            fee_collector,
+            protocol_fee_bps,
+            pricing,
+            presale,
+            clear_presale,
+            max_per_wallet,
+            clear_max_per_wallet,
+            max_initial_inventory,
+        } => handle_update_config(
+            deps,
+            env,
+            info,
+            owner,
+            default_prices,
+            cw20_whitelist,
+            fee_collector,
+            protocol_fee_bps,
+            pricing,
+            presale,
+            clear_presale,
+            max_per_wallet,
+            clear_max_per_wallet,
+            max_initial_inventory,
+        ),
+        HandleMsg::GrantRole { address, role } => handle_grant_role(deps, env, info, address, role),
+        HandleMsg::RevokeRole { address, role } => {
+            handle_revoke_role(deps, env, info, address, role)
+        }
+    }?;
+    // the messages of the draws come first, they were paid in an earlier block
+    res.messages.splice(0..0, drawn.messages);
+    res.attributes.extend(drawn.attributes);
+    Ok(res)
+}
+
+/// whether the address can perform the operations of the role, the owner always can
+fn has_role(deps: Deps, address: &HumanAddr, role: Role) -> StdResult<bool> {
+    let config = config_store_read(deps.storage).load()?;
+    let canonical_addr = deps.api.canonical_address(address)?;
+    if canonical_addr == config.owner {
+        return Ok(true);
+    }
+
+    let roles = roles_store_read(deps.storage)
+        .may_load(canonical_addr.as_slice())?
+        .unwrap_or_default();
+    Ok(roles.contains(&role))
+}
+
+fn check_role(deps: Deps, sender: &HumanAddr, role: Role) -> Result<(), MyCustomError> {
+    if !has_role(deps, sender, role)? {
+        return Err(MyCustomError::MissingRole { role });
+    }
+    Ok(())
+}
+
+pub fn handle_grant_role(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: HumanAddr,
+    role: Role,
+) -> Result<HandleResponse, MyCustomError> {
+    check_owner(deps.as_ref(), &info.sender)?;
+
+    let canonical_addr = deps.api.canonical_address(&address)?;
+    let mut roles = roles_store_read(deps.storage)
+        .may_load(canonical_addr.as_slice())?
+        .unwrap_or_default();
+    if !roles.contains(&role) {
+        roles.push(role);
+    }
+    roles_store(deps.storage).save(canonical_addr.as_slice(), &roles)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::GRANT_ROLE, &env)
+        .from(&info.sender)
+        .to(&address)
+        .attributes();
+    res.attributes
+        .push(attr("role", format!("{:?}", role).to_lowercase()));
+
+    Ok(res)
+}
+
+pub fn handle_revoke_role(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: HumanAddr,
+    role: Role,
+) -> Result<HandleResponse, MyCustomError> {
+    check_owner(deps.as_ref(), &info.sender)?;
+
+    let canonical_addr = deps.api.canonical_address(&address)?;
+    let mut roles = roles_store_read(deps.storage)
+        .may_load(canonical_addr.as_slice())?
+        .unwrap_or_default();
+    roles.retain(|granted| granted != &role);
+    if roles.is_empty() {
+        roles_store(deps.storage).remove(canonical_addr.as_slice());
+    } else {
+        roles_store(deps.storage).save(canonical_addr.as_slice(), &roles)?;
+    }
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::REVOKE_ROLE, &env)
+        .from(&info.sender)
+        .to(&address)
+        .attributes();
+    res.attributes
+        .push(attr("role", format!("{:?}", role).to_lowercase()));
+
+    Ok(res)
+}
+
+/// grow new bonsais in the shop, numbered after every bonsai ever grown
+pub fn handle_grow_bonsais(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    number: u64,
+    prices: Option<Vec<Price>>,
+) -> Result<HandleResponse, MyCustomError> {
+    check_role(deps.as_ref(), &info.sender, Role::Minter)?;
+    let config = config_store_read(deps.storage).load()?;
+    check_inventory(number, config.max_initial_inventory)?;
+    let prices = prices.unwrap_or_else(|| config.default_prices.clone());
+    check_prices(deps.as_ref(), &prices)?;
+
+    let first_id = mint_ids(deps.storage, number)?;
+    let mut bonsai_list = bonsai_store_read(deps.storage).load()?;
+    let first = bonsai_list.bonsais.len();
+    bonsai_list.grow(
+        first_id,
+        number,
+        env.block.height,
+        prices,
+        config.royalty.clone(),
+    );
+    let minter = deps.api.canonical_address(&info.sender)?;
+    draw_traits(&config, &env, &minter, &mut bonsai_list.bonsais[first..]);
+    bonsai_store(deps.storage).save(&bonsai_list)?;
+    for bonsai in bonsai_list.bonsais[first..].iter() {
+        index_bonsai(deps.storage, bonsai, None)?;
+    }
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::GROW_BONSAIS, &env)
+        .from(&info.sender)
+        .attributes();
+    res.attributes.push(attr("number", number));
+
+    Ok(res)
+}
+
+/// take `number` new ids after the whole inventory for bonsais grown right away, returning the first one
+fn mint_ids(storage: &mut dyn Storage, number: u64) -> StdResult<u64> {
+    let mut inventory = inventory_store_read(storage).load()?;
+    let first_id = inventory.total_supply;
+    inventory.total_supply = first_id
+        .checked_add(number)
+        .ok_or_else(|| StdError::generic_err("No bonsai ids left to grow"))?;
+    inventory.minted += number;
+    inventory_store(storage).save(&inventory)?;
+    Ok(first_id)
+}
+
+/// a single transaction grows at least one bonsai and no more than the configured maximum
+fn check_inventory(number: u64, max: u64) -> Result<(), MyCustomError> {
+    if number == 0 {
+        return Err(MyCustomError::EmptyInventory {});
+    }
+    if number > max {
+        return Err(MyCustomError::InventoryTooLarge { max });
+    }
+    Ok(())
+}
+
+/// the bonsai the shop sells under a virtual id, as if it had been grown at init.
+/// None when the id isn't virtual or its record has already been created
+fn virtual_bonsai(storage: &dyn Storage, id: u64) -> StdResult<Option<Bonsai>> {
+    let inventory = inventory_store_read(storage).load()?;
+    if !inventory.is_virtual(id)
+        || grown_store_read(storage)
+            .may_load(&id.to_be_bytes())?
+            .is_some()
+    {
+        return Ok(None);
+    }
+
+    let config = config_store_read(storage).load()?;
+    let mut bonsai = Bonsai::new(id, inventory.virtual_birth_date, config.default_prices);
+    bonsai.royalty = config.royalty;
+    Ok(Some(bonsai))
+}
+
+/// create the record of a virtual bonsai in the shop, from then on it's a grown bonsai
+fn grow_virtual_bonsai(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &CanonicalAddr,
+    mut bonsai: Bonsai,
+) -> StdResult<()> {
+    let config = config_store_read(storage).load()?;
+    draw_traits(&config, env, sender, std::slice::from_mut(&mut bonsai));
+    index_bonsai(storage, &bonsai, None)?;
+    grown_store(storage).save(&bonsai.id.to_be_bytes(), &true)?;
+    let mut inventory = inventory_store_read(storage).load()?;
+    take_virtual_slot(storage, &inventory, bonsai.id)?;
+    inventory.minted += 1;
+    inventory.virtual_grown += 1;
+    inventory_store(storage).save(&inventory)?;
+    bonsai_store(storage).update(|mut bonsai_list| -> StdResult<_> {
+        bonsai_list.bonsais.push(bonsai);
+        Ok(bonsai_list)
+    })?;
+    Ok(())
+}
+
+/// the virtual id not grown yet in the given slot
+fn virtual_id_at(storage: &dyn Storage, inventory: &Inventory, slot: u64) -> StdResult<u64> {
+    Ok(virtual_slots_store_read(storage)
+        .may_load(&slot.to_be_bytes())?
+        .unwrap_or(inventory.first_virtual_id + slot))
+}
+
+/// take a virtual id out of the slots by moving the one in the last slot to its slot,
+/// so a random draw picks among the ids not grown yet in constant time
+fn take_virtual_slot(storage: &mut dyn Storage, inventory: &Inventory, id: u64) -> StdResult<()> {
+    let slot = virtual_positions_store_read(storage)
+        .may_load(&id.to_be_bytes())?
+        .unwrap_or(id - inventory.first_virtual_id);
+    let last = inventory.virtual_left() - 1;
+    if slot != last {
+        let last_id = virtual_id_at(storage, inventory, last)?;
+        virtual_slots_store(storage).save(&slot.to_be_bytes(), &last_id)?;
+        virtual_positions_store(storage).save(&last_id.to_be_bytes(), &slot)?;
+    }
+    virtual_slots_store(storage).remove(&last.to_be_bytes());
+    virtual_positions_store(storage).remove(&id.to_be_bytes());
+    Ok(())
+}
+
+/// draw the traits of freshly grown bonsais from the block, the sender and their ids.
+/// With commit reveal they are drawn once bought, see `handle_reveal_traits`
+fn draw_traits(config: &Config, env: &Env, sender: &CanonicalAddr, bonsais: &mut [Bonsai]) {
+    if config.commit_reveal {
+        return;
+    }
+    for bonsai in bonsais.iter_mut() {
+        let mut prng = Prng::new(&[
+            &env.block.height.to_be_bytes(),
+            &env.block.time.to_be_bytes(),
+            &env.block.time_nanos.to_be_bytes(),
+            sender.as_slice(),
+            &bonsai.id.to_be_bytes(),
+        ]);
+        bonsai.traits = Some(Traits::random(&mut prng));
+    }
+}
+
+/// the first block the contract runs in after another one seeds the draws
+/// committed in the earlier block, which nobody could know when committing.
+/// The random bonsais paid in the earlier block are drawn right away
+fn record_block(mut deps: DepsMut, env: &Env) -> Result<HandleResponse, MyCustomError> {
+    let mut res = HandleResponse::default();
+    let height = env.block.height;
+    let last = last_block_store_read(deps.storage).may_load()?;
+    if matches!(last, Some(last) if last >= height) {
+        return Ok(res);
+    }
+    last_block_store(deps.storage).save(&height)?;
+    let last = match last {
+        Some(last) => last,
+        None => return Ok(res),
+    };
+
+    let seed = BlockSeed {
+        height,
+        time: env.block.time,
+        time_nanos: env.block.time_nanos,
+    };
+    block_seeds_store(deps.storage).save(&last.to_be_bytes(), &seed)?;
+    let buyers = random_draws_store_read(deps.storage)
+        .may_load(&last.to_be_bytes())?
+        .unwrap_or_default();
+    random_draws_store(deps.storage).remove(&last.to_be_bytes());
+    for buyer_addr in buyers.iter() {
+        let drawn = draw_random_bonsai(deps.branch(), env, buyer_addr, &seed)?;
+        res.messages.extend(drawn.messages);
+        res.attributes.extend(drawn.attributes);
+    }
+    Ok(res)
+}
+
+/// whether the message is frozen by the pause scope. The owner operations
+/// are never frozen, so the owner can still fix the shop and unpause it.
+/// A purchase completing an offer is also a transfer, it's checked when buying
+fn is_frozen(scope: &PauseScope, msg: &HandleMsg) -> bool {
+    let purchase = matches!(
+        msg,
+        HandleMsg::BuyBonsai { .. }
+            | HandleMsg::BuyBonsais { .. }
+            | HandleMsg::BuyRandomBonsai {}
+            | HandleMsg::RevealRandomBonsai {}
+            | HandleMsg::Receive(_)
+    );
+    let transfer = matches!(
+        msg,
+        HandleMsg::SellBonsai { .. } | HandleMsg::SellBonsais { .. }
+    );
+
+    match scope {
+        PauseScope::Purchases => purchase,
+        PauseScope::Transfers => transfer,
+        PauseScope::All => {
+            purchase
+                || transfer
+                || matches!(
+                    msg,
+                    HandleMsg::BecomeGardener { .. }
+                        | HandleMsg::CutBonsai { .. }
+                        | HandleMsg::CutBonsais { .. }
+                        | HandleMsg::CancelSale { .. }
+                        | HandleMsg::JoinPresale { .. }
+                        | HandleMsg::CrossBonsais { .. }
+                        | HandleMsg::CommitSeed { .. }
+                        | HandleMsg::RevealTraits { .. }
+                )
+        }
+    }
+}
+
+pub fn handle_pause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    scope: PauseScope,
+) -> Result<HandleResponse, MyCustomError> {
+    check_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+    paused_store(deps.storage).save(&scope)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::PAUSE, &env)
+        .from(&info.sender)
+        .attributes();
+    res.attributes
+        .push(attr("scope", format!("{:?}", scope).to_lowercase()));
+
+    Ok(res)
+}
+
+pub fn handle_unpause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, MyCustomError> {
+    check_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+    paused_store(deps.storage).remove();
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::UNPAUSE, &env)
+        .from(&info.sender)
+        .attributes();
+
+    Ok(res)
+}
+
+/// the config must keep the fees within the sale price and accept only known assets
+fn check_config(deps: Deps, config: &Config) -> Result<(), MyCustomError> {
+    check_prices(deps, &config.default_prices)?;
+    check_inventory(config.max_initial_inventory, MAX_INITIAL_INVENTORY)?;
+
+    // the protocol fee and the royalty are both taken from the same resale price
+    let royalty_bps = config.royalty.as_ref().map_or(0, |royalty| royalty.bps);
+    let fees_bps = config
+        .protocol_fee_bps
+        .checked_add(royalty_bps)
+        .ok_or(MyCustomError::InvalidBasisPoints {})?;
+    if fees_bps > BPS_DENOMINATOR {
+        return Err(MyCustomError::InvalidBasisPoints {});
+    }
+    match config.pricing {
+        PricingMode::DutchAuction { floor_bps, .. } if floor_bps > BPS_DENOMINATOR => {
+            return Err(MyCustomError::InvalidBasisPoints {});
+        }
+        PricingMode::LinearCurve { step_bps } | PricingMode::ExponentialCurve { step_bps }
+            if step_bps > MAX_STEP_BPS =>
+        {
+            return Err(MyCustomError::InvalidBasisPoints {});
+        }
+        _ => {}
+    }
+    if let Some(root) = config
+        .presale
+        .as_ref()
+        .and_then(|presale| presale.merkle_root.as_ref())
+    {
+        decode_hash(root)?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: Option<HumanAddr>,
+    default_prices: Option<Vec<Price>>,
+    cw20_whitelist: Option<Vec<HumanAddr>>,
+    fee_collector: Option<HumanAddr>,
+    protocol_fee_bps: Option<u64>,
+    pricing: Option<PricingMode>,
+    presale: Option<Presale>,
+    clear_presale: bool,
+    max_per_wallet: Option<u64>,
+    clear_max_per_wallet: bool,
+    max_initial_inventory: Option<u64>,
+) -> Result<HandleResponse, MyCustomError> {
+    let mut config = check_owner(deps.as_ref(), &info.sender)?;
+    if (clear_presale && presale.is_some()) || (clear_max_per_wallet && max_per_wallet.is_some()) {
+        return Err(MyCustomError::Std(StdError::generic_err(
+            "A config field can't be set and cleared at once",
+        )));
+    }
+
+    if let Some(tokens) = cw20_whitelist {
+        let cw20_whitelist = tokens
+            .iter()
+            .map(|token| deps.api.canonical_address(token))
+            .collect::<StdResult<Vec<CanonicalAddr>>>()?;
+        cw20_whitelist_store(deps.storage).save(&cw20_whitelist)?;
+    }
+    if let Some(owner) = owner {
+        config.owner = deps.api.canonical_address(&owner)?;
+    }
+    if let Some(default_prices) = default_prices {
+        config.default_prices = default_prices;
+    }
+    if let Some(fee_collector) = fee_collector {
+        config.fee_collector = deps.api.canonical_address(&fee_collector)?;
+    }
+    if let Some(protocol_fee_bps) = protocol_fee_bps {
+        config.protocol_fee_bps = protocol_fee_bps;
+    }
+    if let Some(pricing) = pricing {
+        config.pricing = pricing;
+    }
+    if presale.is_some() || clear_presale {
+        config.presale = presale;
+    }
+    if max_per_wallet.is_some() || clear_max_per_wallet {
+        config.max_per_wallet = max_per_wallet;
+    }
+    if let Some(max_initial_inventory) = max_initial_inventory {
+        config.max_initial_inventory = max_initial_inventory;
+    }
+    check_config(deps.as_ref(), &config)?;
+    config_store(deps.storage).save(&config)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::UPDATE_CONFIG, &env)
+        .from(&info.sender)
+        .attributes();
+
+    Ok(res)
+}
+
+/// prices must be payable: a non zero amount of a named denom or of a whitelisted cw20 token
+fn check_prices(deps: Deps, prices: &[Price]) -> Result<(), MyCustomError> {
+    if prices.is_empty() {
+        return Err(MyCustomError::InvalidPrice {});
+    }
+    let cw20_whitelist = cw20_whitelist_store_read(deps.storage).load()?;
+    for price in prices.iter() {
+        if price.amount().is_zero() {
+            return Err(MyCustomError::InvalidPrice {});
+        }
+        if let Price::Native(coin) = price {
+            if coin.denom.is_empty() {
+                return Err(MyCustomError::InvalidPrice {});
+            }
+        }
+        if let Price::Cw20(coin) = price {
+            if !cw20_whitelist.contains(&deps.api.canonical_address(&coin.address)?) {
+                return Err(MyCustomError::TokenNotWhitelisted {
+                    token: coin.address.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// load the config making sure the sender is the owner of the shop
+fn check_owner(deps: Deps, sender: &HumanAddr) -> Result<Config, MyCustomError> {
+    let config = config_store_read(deps.storage).load()?;
+    let sender_addr = deps.api.canonical_address(sender)?;
+    if sender_addr != config.owner {
+        return Err(MyCustomError::NotCurrentOwner {
+            expected: config.owner,
+            actual: sender_addr,
+        });
+    }
+    Ok(config)
+}
+
+pub fn handle_set_bonsai_price(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    prices: Vec<Price>,
+) -> Result<HandleResponse, MyCustomError> {
+    check_role(deps.as_ref(), &info.sender, Role::PriceManager)?;
+    check_prices(deps.as_ref(), &prices)?;
+
+    // only the bonsais still in the shop can be repriced, a virtual one is grown to keep its price
+    if let Some(bonsai) = virtual_bonsai(deps.storage, id)? {
+        let sender = deps.api.canonical_address(&info.sender)?;
+        grow_virtual_bonsai(deps.storage, &env, &sender, bonsai)?;
+    }
+    let mut bonsai_list = bonsai_store_read(deps.storage).load()?;
+    let bonsai = bonsai_list
+        .bonsais
+        .iter_mut()
+        .find(|bonsai| bonsai.id == id)
+        .ok_or(MyCustomError::BonsaiNotFound { b_id: id })?;
+    bonsai.prices = prices.clone();
+    index_bonsai(deps.storage, bonsai, None)?;
+    bonsai_store(deps.storage).save(&bonsai_list)?;
+
+    let mut res = HandleResponse::default();
+    let event = Event::new(events::SET_BONSAI_PRICE, &env)
+        .bonsai_id(id)
+        .from(&info.sender);
+    res.attributes = prices
+        .iter()
+        .fold(event, |event, price| event.price(price))
+        .attributes();
+
+    Ok(res)
+}
+
+pub fn handle_set_shop_price(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    prices: Vec<Price>,
+) -> Result<HandleResponse, MyCustomError> {
+    check_role(deps.as_ref(), &info.sender, Role::PriceManager)?;
+    check_prices(deps.as_ref(), &prices)?;
+
+    // the virtual bonsais are priced with the default prices
+    config_store(deps.storage).update(|mut config| -> StdResult<_> {
+        config.default_prices = prices.clone();
+        Ok(config)
+    })?;
+
+    let mut bonsai_list = bonsai_store_read(deps.storage).load()?;
+    for bonsai in bonsai_list.bonsais.iter_mut() {
+        bonsai.prices = prices.clone();
+        index_bonsai(deps.storage, bonsai, None)?;
+    }
+    bonsai_store(deps.storage).save(&bonsai_list)?;
+
+    let mut res = HandleResponse::default();
+    let event = Event::new(events::SET_SHOP_PRICE, &env).from(&info.sender);
+    res.attributes = prices
+        .iter()
+        .fold(event, |event, price| event.price(price))
+        .attributes();
+    res.attributes
+        .push(attr("bonsais", bonsai_list.bonsais.len()));
+
+    Ok(res)
+}
+
+pub fn handle_add_sale_event(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_height: u64,
+    end_height: u64,
+    discount_percent: u64,
+) -> Result<HandleResponse, MyCustomError> {
+    check_role(deps.as_ref(), &info.sender, Role::PriceManager)?;
+    if start_height >= end_height || discount_percent > 100 {
+        return Err(MyCustomError::InvalidSaleEvent {});
+    }
+
+    // forget the events that are already over
+    let mut events = sale_events_store_read(deps.storage).load()?;
+    events.retain(|event| event.end_height > env.block.height);
+    events.push(SaleEvent {
+        start_height,
+        end_height,
+        discount_percent,
+    });
+    sale_events_store(deps.storage).save(&events)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::ADD_SALE_EVENT, &env)
+        .from(&info.sender)
+        .attributes();
+    res.attributes.extend(vec![
+        attr("start_height", start_height),
+        attr("end_height", end_height),
+        attr("discount_percent", discount_percent),
+    ]);
+
+    Ok(res)
+}
+
+pub fn handle_update_allowlist(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    add: Vec<HumanAddr>,
+    remove: Vec<HumanAddr>,
+) -> Result<HandleResponse, MyCustomError> {
+    check_owner(deps.as_ref(), &info.sender)?;
+
+    for address in add.iter() {
+        let canonical_addr = deps.api.canonical_address(address)?;
+        allowlist_store(deps.storage).save(canonical_addr.as_slice(), &true)?;
+    }
+    for address in remove.iter() {
+        let canonical_addr = deps.api.canonical_address(address)?;
+        allowlist_store(deps.storage).remove(canonical_addr.as_slice());
+    }
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::UPDATE_ALLOWLIST, &env)
+        .from(&info.sender)
+        .attributes();
+    res.attributes.extend(vec![
+        attr("added", add.len()),
+        attr("removed", remove.len()),
+    ]);
+
+    Ok(res)
+}
+
+/// add the sender to the presale allowlist proving it's part of the merkle root
+pub fn handle_join_presale(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proof: Vec<String>,
+) -> Result<HandleResponse, MyCustomError> {
+    let config = config_store_read(deps.storage).load()?;
+    let presale = config
+        .presale
+        .ok_or_else(|| MyCustomError::Std(StdError::generic_err("There is no presale to join")))?;
+    if !presale.verify(&info.sender, &proof)? {
+        return Err(MyCustomError::InvalidMerkleProof {});
+    }
+
+    let canonical_addr = deps.api.canonical_address(&info.sender)?;
+    allowlist_store(deps.storage).save(canonical_addr.as_slice(), &true)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::JOIN_PRESALE, &env)
+        .from(&info.sender)
+        .attributes();
+
+    Ok(res)
+}
+
+/// commit to a secret for a bonsai bought with commit reveal and not revealed yet.
+/// The traits will also depend on the next block, which the owner can't know yet
+pub fn handle_commit_seed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    b_id: u64,
+    commitment: String,
+) -> Result<HandleResponse, MyCustomError> {
+    decode_hash(&commitment)?;
+    let canonical_addr = deps.api.canonical_address(&info.sender)?;
+    let gardener = gardeners_store_read(deps.storage)
+        .may_load(canonical_addr.as_slice())?
+        .ok_or(MyCustomError::GardenerNotFound {})?;
+    let bonsai = gardener
+        .bonsais
+        .iter()
+        .find(|bonsai| bonsai.id == b_id)
+        .ok_or(MyCustomError::NotBonsaiOwner { b_id })?;
+    if bonsai.traits.is_some() {
+        return Err(MyCustomError::AlreadyRevealed { b_id });
+    }
+
+    let mut pending = seed_commitments_store_read(deps.storage)
+        .may_load(&b_id.to_be_bytes())?
+        .ok_or(MyCustomError::InvalidCommitment {})?;
+    if pending.commitment.is_some() {
+        return Err(MyCustomError::AlreadyCommitted { b_id });
+    }
+    if env.block.height >= pending.deadline {
+        return Err(MyCustomError::RevealExpired { b_id });
+    }
+    pending.commitment = Some(commitment);
+    pending.height = env.block.height;
+    seed_commitments_store(deps.storage).save(&b_id.to_be_bytes(), &pending)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::COMMIT_SEED, &env)
+        .bonsai_id(b_id)
+        .from(&info.sender)
+        .attributes();
+
+    Ok(res)
+}
+
+/// reveal the traits of a bought bonsai, drawn from the secret and from the first
+/// block after the commitment. Whoever knows the secret reveals them before the deadline,
+/// after it anyone can, without the secret, and the bonsai gets common traits
+pub fn handle_reveal_traits(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    b_id: u64,
+    secret: Option<String>,
+) -> Result<HandleResponse, MyCustomError> {
+    let entry = search_entries_store_read(deps.storage)
+        .may_load(&b_id.to_be_bytes())?
+        .ok_or(MyCustomError::BonsaiNotFound { b_id })?;
+    if entry.bonsai.traits.is_some() {
+        return Err(MyCustomError::AlreadyRevealed { b_id });
+    }
+    let pending = seed_commitments_store_read(deps.storage)
+        .may_load(&b_id.to_be_bytes())?
+        .ok_or(MyCustomError::InvalidCommitment {})?;
+    // a seed recorded by this very transaction could be drawn again in the next
+    // block by reverting it, so the seed has to come from an earlier block
+    let seed = block_seeds_store_read(deps.storage)
+        .may_load(&pending.height.to_be_bytes())?
+        .filter(|seed| seed.height < env.block.height)
+        .ok_or(MyCustomError::InvalidCommitment {})?;
+    let seed_parts: [&[u8]; 4] = [
+        &seed.height.to_be_bytes(),
+        &seed.time.to_be_bytes(),
+        &seed.time_nanos.to_be_bytes(),
+        &b_id.to_be_bytes(),
+    ];
+
+    let expired = env.block.height >= pending.deadline;
+    let traits = match secret {
+        Some(_) if expired => return Err(MyCustomError::RevealExpired { b_id }),
+        Some(secret) => {
+            if !pending.is_revealed_by(&secret)? {
+                return Err(MyCustomError::InvalidCommitment {});
+            }
+            let mut parts = vec![secret.as_bytes()];
+            parts.extend_from_slice(&seed_parts);
+            Traits::random(&mut Prng::new(&parts))
+        }
+        None if expired => Traits::forfeited(&mut Prng::new(&seed_parts)),
+        None => return Err(MyCustomError::RevealNotExpired { b_id }),
+    };
+
+    let owner = entry.owner.ok_or(MyCustomError::InvalidCommitment {})?;
+    let mut gardener = gardeners_store_read(deps.storage)
+        .may_load(owner.as_slice())?
+        .ok_or(MyCustomError::GardenerNotFound {})?;
+    let bonsai = gardener
+        .bonsais
+        .iter_mut()
+        .find(|bonsai| bonsai.id == b_id)
+        .ok_or(MyCustomError::BonsaiNotFound { b_id })?;
+    bonsai.traits = Some(traits);
+    index_bonsai(deps.storage, bonsai, Some(&owner))?;
+    gardeners_store(deps.storage).save(owner.as_slice(), &gardener)?;
+    seed_commitments_store(deps.storage).remove(&b_id.to_be_bytes());
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::REVEAL_TRAITS, &env)
+        .bonsai_id(b_id)
+        .from(&info.sender)
+        .attributes();
+    res.attributes.extend(trait_attributes(&traits));
+
+    Ok(res)
+}
+
+/// a gardener can buy from the shop only once the presale is over or if allowlisted,
+/// and without going over the purchase limit counting the `pending` bonsais of the batch
+fn check_shop_purchase(
+    deps: Deps,
+    buyer: &CanonicalAddr,
+    height: u64,
+    pending: u64,
+) -> Result<(), MyCustomError> {
+    let config = config_store_read(deps.storage).load()?;
+    if let Some(presale) = config.presale {
+        let allowlisted = allowlist_store_read(deps.storage)
+            .may_load(buyer.as_slice())?
+            .unwrap_or(false);
+        if presale.is_active(height) && !allowlisted {
+            return Err(MyCustomError::NotAllowlisted {});
+        }
+    }
+
+    if let Some(limit) = config.max_per_wallet {
+        let purchased = gardeners_store_read(deps.storage)
+            .may_load(buyer.as_slice())?
+            .map_or(0, |gardener| gardener.purchased);
+        let paid = random_purchases_store_read(deps.storage)
+            .may_load(buyer.as_slice())?
+            .is_some() as u64;
+        if purchased + paid + pending >= limit {
+            return Err(MyCustomError::PurchaseLimitReached { limit });
+        }
+    }
+    Ok(())
+}
+
+/// the prices of a shop bonsai at the given height, following the pricing mode
+/// and the active sale events, together with the applied discount. The `pending`
+/// bonsais of the same batch count as already sold
+fn shop_prices(
+    storage: &dyn Storage,
+    bonsai: &Bonsai,
+    height: u64,
+    pending: u64,
+) -> Result<(Vec<Price>, u64), MyCustomError> {
+    let config = config_store_read(storage).load()?;
+    let sold = sold_store_read(storage).load()? + pending;
+    let events = sale_events_store_read(storage).load()?;
+    let discount = active_discount(&events, height);
+
+    let prices = bonsai
+        .shop_prices(&config.pricing, sold, height)?
+        .iter()
+        .map(|price| {
+            mul_div(price.amount().u128(), (100 - discount) as u128, 100)
+                .map(|amount| price.with_amount(Uint128(amount)))
+                .ok_or(MyCustomError::PriceOverflow {})
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((prices, discount))
+}
+
+pub fn handle_become_gardener(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<HandleResponse, MyCustomError> {
+    let canonical_addr = &deps.api.canonical_address(&info.sender)?;
+    let res = gardeners_store(deps.storage).load(canonical_addr.as_slice());
+    let gardener = match res {
+        Ok(_) => {
+            return Err(MyCustomError::Std(StdError::generic_err(
+                "A gardener with the sender address already exist",
+            )));
+        }
+        Err(_) => Gardener::new(name, canonical_addr.clone(), vec![]),
+    };
+
+    gardeners_store(deps.storage).save(canonical_addr.as_slice(), &gardener)?;
+    update_scores(deps.storage, canonical_addr, None)?;
+    stats_store(deps.storage).update(|mut stats| -> StdResult<_> {
+        stats.gardeners += 1;
+        Ok(stats)
+    })?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::BECOME_GARDENER, &env)
+        .from(&info.sender)
+        .attributes();
+
+    Ok(res)
+}
+
+/// a batch must name at least one bonsai and each of them only once
+fn check_batch(ids: &[u64]) -> Result<(), MyCustomError> {
+    if ids.is_empty() {
+        return Err(MyCustomError::EmptyBatch {});
+    }
+    let mut seen = BTreeSet::new();
+    for id in ids.iter() {
+        if !seen.insert(id) {
+            return Err(MyCustomError::DuplicatedBonsai { b_id: *id });
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_buy_bonsai(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ids: Vec<u64>,
+) -> Result<HandleResponse, MyCustomError> {
+    // check if the gardener sent some funds to buy the bonsais
+    if info.sent_funds.is_empty() {
+        return Err(MyCustomError::Std(StdError::generic_err(
+            "No funds to complete the purchase",
+        )));
+    }
+    let payments: Vec<Price> = info.sent_funds.into_iter().map(Price::from).collect();
+
+    buy_bonsais(deps, env, info.sender, &payments, &ids)
+}
+
+/// entry point for the cw20 `Send` hook: the sending token contract is the
+/// message sender while the buyer is the original sender of the tokens
+pub fn handle_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<HandleResponse, MyCustomError> {
+    let token_addr = deps.api.canonical_address(&info.sender)?;
+    let cw20_whitelist = cw20_whitelist_store_read(deps.storage).load()?;
+    if !cw20_whitelist.contains(&token_addr) {
+        return Err(MyCustomError::TokenNotWhitelisted { token: info.sender });
+    }
+
+    let msg: ReceiveMsg = match wrapper.msg {
+        Some(bin) => from_binary(&bin)?,
+        None => {
+            return Err(MyCustomError::Std(StdError::generic_err(
+                "No message to execute with the received tokens",
+            )))
+        }
+    };
+    let payment = Price::Cw20(Cw20CoinHuman {
+        address: info.sender,
+        amount: wrapper.amount,
+    });
+
+    match msg {
+        ReceiveMsg::BuyBonsai { b_id } => {
+            buy_bonsais(deps, env, wrapper.sender, &[payment], &[b_id])
+        }
+        ReceiveMsg::BuyBonsais { b_ids } => {
+            buy_bonsais(deps, env, wrapper.sender, &[payment], &b_ids)
+        }
+    }
+}
+
+/// a purchase checked against the storage before any bonsai is moved
+struct Purchase {
+    bonsai: Bonsai,
+    from: BonsaiOwner,
+    price: Price,
+    // the record to create first when the bonsai is bought under a virtual id
+    grown: Option<Bonsai>,
+}
+
+/// move bonsais from the shop, or from gardeners who offered them to the buyer,
+/// once the payments cover the sum of their prices. Every purchase is checked before
+/// moving the first bonsai, so a batch is either bought entirely or not at all.
+/// The protocol fee is paid on every sale, while royalties and seller proceeds only
+/// apply to resales. What's left of the payments is sent back to the buyer
+fn buy_bonsais(
+    deps: DepsMut,
+    env: Env,
+    buyer: HumanAddr,
+    payments: &[Price],
+    ids: &[u64],
+) -> Result<HandleResponse, MyCustomError> {
+    check_batch(ids)?;
+    let buyer_addr = deps.api.canonical_address(&buyer)?;
+
+    // try to load bonsai list if present otherwise returns error
+    let bonsai_list = bonsai_store_read(deps.storage).load()?;
+
+    let mut remaining = payments.to_vec();
+    let mut purchases = Vec::with_capacity(ids.len());
+    let mut shop_purchases = 0;
+    let transfers_paused = matches!(
+        paused_store_read(deps.storage).may_load()?,
+        Some(PauseScope::Transfers)
+    );
+    for &id in ids.iter() {
+        let height = env.block.height;
+        let shop_bonsai = match bonsai_list.bonsais.iter().find(|bonsai| bonsai.id == id) {
+            Some(bonsai) => Some((bonsai.clone(), None)),
+            None => virtual_bonsai(deps.storage, id)?.map(|bonsai| (bonsai.clone(), Some(bonsai))),
+        };
+        let (bonsai, from, grown) = match shop_bonsai {
+            Some((mut bonsai, grown)) => {
+                check_shop_purchase(deps.as_ref(), &buyer_addr, height, shop_purchases)?;
+                // the shop sells at the price given by the pricing mode and the sale events
+                bonsai.prices = shop_prices(deps.storage, &bonsai, height, shop_purchases)?.0;
+                shop_purchases += 1;
+                (bonsai, BonsaiOwner::Shop, grown)
+            }
+            None => {
+                if transfers_paused {
+                    return Err(MyCustomError::ContractPaused {});
+                }
+                let offer = sale_offers_store_read(deps.storage)
+                    .may_load(&id.to_be_bytes())?
+                    .filter(|offer| offer.recipient == buyer_addr)
+                    .ok_or(MyCustomError::NotOnSale { b_id: id })?;
+                let mut bonsai = gardeners_store_read(deps.storage)
+                    .may_load(offer.seller.as_slice())?
+                    .and_then(|seller| seller.bonsais.into_iter().find(|bonsai| bonsai.id == id))
+                    .ok_or(MyCustomError::NotOnSale { b_id: id })?;
+                // a resale is paid at the prices asked by the seller
+                bonsai.prices = offer.prices;
+                (bonsai, BonsaiOwner::Gardener(offer.seller), None)
+            }
+        };
+
+        // check if the gardener has enough funds left to buy the bonsai
+        let price = bonsai.matching_price(&remaining).cloned().ok_or_else(|| {
+            MyCustomError::Std(StdError::generic_err(
+                "Insufficient funds to buy the bonsai",
+            ))
+        })?;
+        if let Some(payment) = remaining
+            .iter_mut()
+            .find(|payment| price.is_covered_by(payment))
+        {
+            *payment = payment.with_amount((payment.amount() - price.amount())?);
+        }
+        purchases.push(Purchase {
+            bonsai,
+            from,
+            price,
+            grown,
+        });
+    }
+
+    let config = config_store_read(deps.storage).load()?;
+    let fee_collector = deps.api.human_address(&config.fee_collector)?;
 
-// init is like the genesis of cosmos SDK
-pub fn init(
+    let mut res = HandleResponse::default();
+    for Purchase {
+        bonsai,
+        from,
+        price,
+        grown,
+    } in purchases
+    {
+        let id = bonsai.id;
+        if let Some(record) = grown {
+            grow_virtual_bonsai(deps.storage, &env, &buyer_addr, record)?;
+        }
+
+        // move the bought bonsai to the buyer's garden
+        sale_offers_store(deps.storage).remove(&id.to_be_bytes());
+        transfer_bonsai(
+            deps.storage,
+            &from,
+            &BonsaiOwner::Gardener(buyer_addr.clone()),
+            id,
+        )?;
+        let (kind, seller_addr) = match &from {
+            BonsaiOwner::Shop => {
+                record_shop_sale(deps.storage, &buyer_addr, id, env.block.height)?;
+                (HistoryKind::Buy, None)
+            }
+            BonsaiOwner::Gardener(seller_addr) => {
+                (HistoryKind::Transfer, Some(seller_addr.clone()))
+            }
+        };
+        record_history(
+            deps.storage,
+            id,
+            kind,
+            seller_addr,
+            Some(buyer_addr.clone()),
+            Some(price.clone()),
+            env.block.height,
+        )?;
+        record_sale(deps.storage, &price)?;
+        update_scores(deps.storage, &buyer_addr, Some(&price))?;
+        if let BonsaiOwner::Gardener(seller_addr) = &from {
+            update_scores(deps.storage, seller_addr, None)?;
+        }
+
+        let protocol_fee = config.protocol_fee(price.amount());
+        let seller = match &from {
+            BonsaiOwner::Shop => env.contract.address.clone(),
+            BonsaiOwner::Gardener(seller_addr) => deps.api.human_address(seller_addr)?,
+        };
+        res.attributes.extend(
+            Event::new(events::BUY_BONSAI, &env)
+                .bonsai_id(id)
+                .from(&seller)
+                .to(&buyer)
+                .price(&price)
+                .attributes(),
+        );
+        res.attributes.push(attr("protocol_fee", protocol_fee));
+        if !protocol_fee.is_zero() {
+            res.messages.push(payout(
+                &env,
+                &price.with_amount(protocol_fee),
+                fee_collector.clone(),
+            )?);
+        }
+
+        if let BonsaiOwner::Gardener(_) = from {
+            let mut seller_amount = (price.amount() - protocol_fee)?;
+
+            if let Some(royalty) = bonsai.royalty {
+                let royalty_amount = royalty.amount(price.amount());
+                if !royalty_amount.is_zero() {
+                    let recipient = deps.api.human_address(&royalty.recipient)?;
+                    res.messages
+                        .push(payout(&env, &price.with_amount(royalty_amount), recipient)?);
+                    seller_amount = (seller_amount - royalty_amount)?;
+                }
+                res.attributes.push(attr("royalty", royalty_amount));
+            }
+
+            if !seller_amount.is_zero() {
+                res.messages
+                    .push(payout(&env, &price.with_amount(seller_amount), seller)?);
+            }
+            res.attributes.push(attr("seller_amount", seller_amount));
+        }
+    }
+
+    // the funds sent over the prices go back to the buyer
+    refund(&env, &remaining, &buyer, &mut res)?;
+
+    Ok(res)
+}
+
+/// count a bonsai sold by the shop, for the pricing curve and the buyer's purchase limit.
+/// With commit reveal the buyer can now commit to a secret to draw its traits
+fn record_shop_sale(
+    storage: &mut dyn Storage,
+    buyer: &CanonicalAddr,
+    b_id: u64,
+    height: u64,
+) -> Result<(), MyCustomError> {
+    if config_store_read(storage).load()?.commit_reveal {
+        seed_commitments_store(storage).save(
+            &b_id.to_be_bytes(),
+            &SeedCommitment {
+                commitment: None,
+                height,
+                deadline: height + REVEAL_DEADLINE_BLOCKS,
+            },
+        )?;
+    }
+    sold_store(storage).update(|sold| -> StdResult<u64> { Ok(sold + 1) })?;
+    gardeners_store(storage).update(buyer.as_slice(), |gardener| {
+        let mut gardener = gardener.ok_or(MyCustomError::GardenerNotFound {})?;
+        gardener.purchased += 1;
+        Ok::<_, MyCustomError>(gardener)
+    })?;
+    Ok(())
+}
+
+fn record_sale(storage: &mut dyn Storage, price: &Price) -> StdResult<()> {
+    stats_store(storage).update(|mut stats| -> StdResult<_> {
+        stats.record_sale(price);
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+/// bonsais the shop can still sell, grown or virtual
+fn shop_left(storage: &dyn Storage) -> StdResult<u64> {
+    let bonsai_list = bonsai_store_read(storage).load()?;
+    let inventory = inventory_store_read(storage).load()?;
+    Ok(bonsai_list.bonsais.len() as u64 + inventory.virtual_left())
+}
+
+/// pay the shop price of a bonsai grown with the default prices. The bonsai is only
+/// drawn by the first transaction of a later block, so it can't be picked by reading
+/// the shop first, and the funds sent over the price are given back
+pub fn handle_buy_random_bonsai(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
-    msg: InitMsg,
-) -> Result<InitResponse, MyCustomError> {
-    // set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    let bonsai_list = BonsaiList::grow_bonsais(msg.number, env.block.height, msg.price);
-    bonsai_store(deps.storage).save(&bonsai_list)?;
-    let mut res = InitResponse::default();
-    res.attributes = vec![attr("action", "grown_bonsais")];
+    info: MessageInfo,
+) -> Result<HandleResponse, MyCustomError> {
+    if info.sent_funds.is_empty() {
+        return Err(MyCustomError::Std(StdError::generic_err(
+            "No funds to complete the purchase",
+        )));
+    }
+    let payments: Vec<Price> = info.sent_funds.into_iter().map(Price::from).collect();
+
+    let buyer_addr = deps.api.canonical_address(&info.sender)?;
+    if random_purchases_store_read(deps.storage)
+        .may_load(buyer_addr.as_slice())?
+        .is_some()
+    {
+        return Err(MyCustomError::PendingRandomPurchase {});
+    }
+    if gardeners_store_read(deps.storage)
+        .may_load(buyer_addr.as_slice())?
+        .is_none()
+    {
+        return Err(MyCustomError::GardenerNotFound {});
+    }
+    let height = env.block.height;
+    check_shop_purchase(deps.as_ref(), &buyer_addr, height, 0)?;
+    if shop_left(deps.storage)? == 0 {
+        return Err(MyCustomError::SoldOut {});
+    }
+    let mut buyers = random_draws_store_read(deps.storage)
+        .may_load(&height.to_be_bytes())?
+        .unwrap_or_default();
+    if buyers.len() as u64 >= MAX_RANDOM_DRAWS_PER_BLOCK {
+        return Err(MyCustomError::RandomDrawsFull {});
+    }
+
+    let config = config_store_read(deps.storage).load()?;
+    let mut bonsai = Bonsai::new(0, height, config.default_prices);
+    bonsai.prices = shop_prices(deps.storage, &bonsai, height, 0)?.0;
+    let price = bonsai.matching_price(&payments).cloned().ok_or_else(|| {
+        MyCustomError::Std(StdError::generic_err(
+            "Insufficient funds to buy the bonsai",
+        ))
+    })?;
+    let mut remaining = payments;
+    if let Some(payment) = remaining
+        .iter_mut()
+        .find(|payment| price.is_covered_by(payment))
+    {
+        *payment = payment.with_amount((payment.amount() - price.amount())?);
+    }
+
+    random_purchases_store(deps.storage).save(
+        buyer_addr.as_slice(),
+        &RandomPurchase {
+            price: price.clone(),
+            height,
+        },
+    )?;
+    buyers.push(buyer_addr);
+    random_draws_store(deps.storage).save(&height.to_be_bytes(), &buyers)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::BUY_RANDOM_BONSAI, &env)
+        .from(&info.sender)
+        .to(&env.contract.address)
+        .price(&price)
+        .attributes();
+    refund(&env, &remaining, &info.sender, &mut res)?;
+
     Ok(res)
 }
 
-pub fn handle(
+/// the random bonsais paid in earlier blocks are drawn by the first transaction of
+/// a later block, whichever it is. This one only fails while the sender's isn't drawn yet
+pub fn handle_reveal_random_bonsai(
     deps: DepsMut,
-    _: Env,
+    _env: Env,
     info: MessageInfo,
-    msg: HandleMsg,
 ) -> Result<HandleResponse, MyCustomError> {
-    match msg {
-        HandleMsg::BecomeGardener { name } => handle_become_gardener(deps, info, name),
-        HandleMsg::BuyBonsai { b_id } => handle_buy_bonsai(deps, info, b_id),
-        HandleMsg::SellBonsai { recipient, b_id } => {
-            handle_sell_bonsai(deps, info, recipient, b_id)
-        }
-        HandleMsg::CutBonsai { b_id } => handle_cut_bonsai(deps, info, b_id),
+    let buyer_addr = deps.api.canonical_address(&info.sender)?;
+    if random_purchases_store_read(deps.storage)
+        .may_load(buyer_addr.as_slice())?
+        .is_some()
+    {
+        return Err(MyCustomError::PendingRandomPurchase {});
     }
+    Ok(HandleResponse::default())
 }
 
-pub fn handle_become_gardener(
+/// draw the random bonsai paid by the buyer in the block the seed follows, before
+/// anything else changes the shop. It's drawn among the shop bonsais whose current
+/// price is covered by the price paid, and the difference is given back
+fn draw_random_bonsai(
     deps: DepsMut,
-    info: MessageInfo,
-    name: String,
+    env: &Env,
+    buyer_addr: &CanonicalAddr,
+    seed: &BlockSeed,
 ) -> Result<HandleResponse, MyCustomError> {
-    let canonical_addr = &deps.api.canonical_address(&info.sender)?;
-    let res = gardeners_store(deps.storage).load(canonical_addr.as_slice());
-    let gardener = match res {
-        Ok(_) => {
-            return Err(MyCustomError::Std(StdError::generic_err(
-                "A gardener with the sender address already exist",
-            )));
+    let purchase = random_purchases_store_read(deps.storage).load(buyer_addr.as_slice())?;
+    random_purchases_store(deps.storage).remove(buyer_addr.as_slice());
+    let buyer = deps.api.human_address(buyer_addr)?;
+    let height = env.block.height;
+
+    // the current price of a shop bonsai in the asset paid, if the payment covers it
+    let covered = |bonsai: &Bonsai| -> Result<Option<Price>, MyCustomError> {
+        let prices = match shop_prices(deps.storage, bonsai, height, 0) {
+            Ok((prices, _)) => prices,
+            Err(MyCustomError::PriceOverflow {}) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Ok(prices
+            .into_iter()
+            .find(|price| price.is_covered_by(&purchase.price)))
+    };
+    let bonsai_list = bonsai_store_read(deps.storage).load()?;
+    let mut candidates = vec![];
+    for bonsai in bonsai_list.bonsais.iter() {
+        if let Some(price) = covered(bonsai)? {
+            candidates.push((bonsai.id, price));
         }
-        Err(_) => Gardener::new(name, canonical_addr.clone(), vec![]),
+    }
+    // the virtual bonsais all have the default prices and the same birth date
+    let inventory = inventory_store_read(deps.storage).load()?;
+    let config = config_store_read(deps.storage).load()?;
+    let virtual_price = match inventory.virtual_left() {
+        0 => None,
+        _ => covered(&Bonsai::new(
+            inventory.first_virtual_id,
+            inventory.virtual_birth_date,
+            config.default_prices.clone(),
+        ))?,
     };
-
-    gardeners_store(deps.storage).save(canonical_addr.as_slice(), &gardener)?;
+    let virtual_left = virtual_price
+        .as_ref()
+        .map_or(0, |_| inventory.virtual_left());
 
     let mut res = HandleResponse::default();
-    res.attributes = vec![
-        attr("action", "become_gardener"),
-        attr("gardener_addr", info.sender),
-    ];
+    let left = candidates.len() as u64 + virtual_left;
+    if left == 0 {
+        res.messages
+            .push(payout(env, &purchase.price, buyer.clone())?);
+        res.attributes = Event::new(events::REFUND_RANDOM_BONSAI, env)
+            .from(&env.contract.address)
+            .to(&buyer)
+            .price(&purchase.price)
+            .attributes();
+        return Ok(res);
+    }
+
+    let mut prng = Prng::new(&[
+        &purchase.height.to_be_bytes(),
+        &seed.height.to_be_bytes(),
+        &seed.time.to_be_bytes(),
+        &seed.time_nanos.to_be_bytes(),
+        buyer_addr.as_slice(),
+    ]);
+    let draw = prng.below(left);
+    let (id, price) = match candidates.get(draw as usize) {
+        Some((id, price)) => (*id, price.clone()),
+        None => {
+            let slot = draw - candidates.len() as u64;
+            let id = virtual_id_at(deps.storage, &inventory, slot)?;
+            let bonsai = virtual_bonsai(deps.storage, id)?
+                .ok_or(MyCustomError::BonsaiNotFound { b_id: id })?;
+            grow_virtual_bonsai(deps.storage, env, buyer_addr, bonsai)?;
+            (id, virtual_price.unwrap_or_else(|| purchase.price.clone()))
+        }
+    };
+
+    transfer_bonsai(
+        deps.storage,
+        &BonsaiOwner::Shop,
+        &BonsaiOwner::Gardener(buyer_addr.clone()),
+        id,
+    )?;
+    record_shop_sale(deps.storage, buyer_addr, id, height)?;
+    record_history(
+        deps.storage,
+        id,
+        HistoryKind::Buy,
+        None,
+        Some(buyer_addr.clone()),
+        Some(price.clone()),
+        height,
+    )?;
+    record_sale(deps.storage, &price)?;
+    update_scores(deps.storage, buyer_addr, Some(&price))?;
+
+    let protocol_fee = config.protocol_fee(price.amount());
+    if !protocol_fee.is_zero() {
+        let fee_collector = deps.api.human_address(&config.fee_collector)?;
+        res.messages.push(payout(
+            env,
+            &price.with_amount(protocol_fee),
+            fee_collector,
+        )?);
+    }
+    res.attributes = Event::new(events::REVEAL_RANDOM_BONSAI, env)
+        .bonsai_id(id)
+        .from(&env.contract.address)
+        .to(&buyer)
+        .price(&price)
+        .attributes();
+    res.attributes.push(attr("protocol_fee", protocol_fee));
+    let change = purchase
+        .price
+        .with_amount((purchase.price.amount() - price.amount())?);
+    refund(env, &[change], &buyer, &mut res)?;
 
     Ok(res)
 }
 
-fn remove_bonsai(deps: DepsMut, address: CanonicalAddr, bonsai_id: u64) {
-    let _ = gardeners_store(deps.storage).update::<_, StdError>(
-        address.as_slice(),
-        |seller_gardener| {
-            let mut unwrapped = seller_gardener.unwrap();
-            unwrapped.bonsais.retain(|b| b.id != bonsai_id);
-            Ok(unwrapped)
-        },
-    );
+/// give back to the buyer what's left of the payments
+fn refund(
+    env: &Env,
+    remaining: &[Price],
+    buyer: &HumanAddr,
+    res: &mut HandleResponse,
+) -> StdResult<()> {
+    for payment in remaining
+        .iter()
+        .filter(|payment| !payment.amount().is_zero())
+    {
+        res.messages.push(payout(env, payment, buyer.clone())?);
+        res.attributes.push(attr("refund", payment.amount()));
+    }
+    Ok(())
 }
 
-pub fn handle_buy_bonsai(
+/// send the given amount, either native coins or cw20 tokens, from the contract to the recipient
+fn payout(env: &Env, amount: &Price, recipient: HumanAddr) -> StdResult<CosmosMsg> {
+    match amount {
+        Price::Native(coin) => Ok(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: recipient,
+            amount: vec![coin.clone()],
+        }
+        .into()),
+        Price::Cw20(coin) => Ok(WasmMsg::Execute {
+            contract_addr: coin.address.clone(),
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient,
+                amount: coin.amount,
+            })?,
+            send: vec![],
+        }
+        .into()),
+    }
+}
+
+/// offer bonsais of the sender to the buyer, who completes the sale with `BuyBonsai`
+/// paying one of the asking prices. A new offer for a bonsai replaces the previous one
+pub fn handle_sell_bonsai(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    id: u64,
+    buyer: HumanAddr,
+    ids: Vec<u64>,
+    prices: Vec<Price>,
 ) -> Result<HandleResponse, MyCustomError> {
-    // try to load bonsai list if present otherwise returns error
-    let bonsai_list = bonsai_store(deps.storage).load()?;
+    check_batch(&ids)?;
+    check_prices(deps.as_ref(), &prices)?;
 
-    let bonsai = bonsai_list.bonsais.iter().find(|bonsai| bonsai.id == id);
-
-    let bonsai = match bonsai {
-        Some(bonsai) => bonsai,
-        None => return Err(MyCustomError::Std(StdError::not_found("Bonsai not found"))),
-    };
+    // convert human_addr to canonical
+    let seller_addr = &deps.api.canonical_address(&info.sender)?;
+    let seller = gardeners_store_read(deps.storage)
+        .may_load(seller_addr.as_slice())?
+        .ok_or(MyCustomError::GardenerNotFound {})?;
 
-    // check if the gardener has enough funds to buy the bonsai
-    let sent_funds = info.sent_funds.first().ok_or_else(|| {
-        MyCustomError::Std(StdError::generic_err("No funds to complete the purchase"))
-    })?;
-    if sent_funds.denom == bonsai.price.denom {
-        if sent_funds.amount < bonsai.price.amount {
-            return Err(MyCustomError::Std(StdError::generic_err(
-                "Insufficient funds to buy the bonsai",
-            )));
+    // check every bonsai to sell before offering any of them
+    for &id in ids.iter() {
+        if !seller.bonsais.iter().any(|bonsai| bonsai.id == id) {
+            return Err(MyCustomError::NotBonsaiOwner { b_id: id });
         }
-    } else {
+    }
+
+    // don't offer to a buyer who can't pay any asking price right now, the payment
+    // itself is only checked when the buyer completes the sale
+    let balances = prices
+        .iter()
+        .map(|price| query_price_balance(&deps.querier, &buyer, price))
+        .collect::<StdResult<Vec<Price>>>()?;
+    if !prices
+        .iter()
+        .any(|price| balances.iter().any(|balance| price.is_covered_by(balance)))
+    {
         return Err(MyCustomError::Std(StdError::generic_err(
-            "Insufficient funds to buy the bonsai",
+            "Insufficient buyers funds",
         )));
     }
 
-    // remove the bought bonsai from the garden
-    bonsai_store(deps.storage).update::<_, StdError>(|mut bonsai_list| {
-        bonsai_list.bonsais.retain(|bonsai| bonsai.id != id);
-        Ok(bonsai_list)
-    })?;
-
-    let canonical_addr = &deps.api.canonical_address(&info.sender)?;
-    // todo check if it's possible to use may_update
-    gardeners_store(deps.storage).update::<_, StdError>(canonical_addr.as_slice(), |gardener| {
-        let mut unwrapped = gardener.unwrap();
-        unwrapped.bonsais.push(bonsai.clone());
-        Ok(unwrapped)
-    })?;
+    // the buyer completes the sale paying for the bonsais
+    let buyer_addr = deps.api.canonical_address(&buyer)?;
+    if &buyer_addr == seller_addr {
+        return Err(MyCustomError::AlreadyOwned { b_id: ids[0] });
+    }
+    if gardeners_store_read(deps.storage)
+        .may_load(buyer_addr.as_slice())?
+        .is_none()
+    {
+        return Err(MyCustomError::GardenerNotFound {});
+    }
 
     let mut res = HandleResponse::default();
-    res.attributes = vec![
-        attr("action", "buy_bonsai"),
-        attr("buyer", info.sender),
-        attr("amount", bonsai.price.amount),
-    ];
+    for &id in ids.iter() {
+        let offer = SaleOffer {
+            seller: seller_addr.clone(),
+            recipient: buyer_addr.clone(),
+            prices: prices.clone(),
+        };
+        sale_offers_store(deps.storage).save(&id.to_be_bytes(), &offer)?;
+        reindex_bonsai(deps.storage, id)?;
+        record_history(
+            deps.storage,
+            id,
+            HistoryKind::Sell,
+            Some(seller_addr.clone()),
+            Some(buyer_addr.clone()),
+            None,
+            env.block.height,
+        )?;
+
+        let event = Event::new(events::SELL_BONSAI, &env)
+            .bonsai_id(id)
+            .from(&info.sender)
+            .to(&buyer);
+        res.attributes.extend(
+            prices
+                .iter()
+                .fold(event, |event, price| event.price(price))
+                .attributes(),
+        );
+    }
 
     Ok(res)
 }
 
-pub fn handle_sell_bonsai(
+/// withdraw the offer made by the sender for one of their bonsais
+pub fn handle_cancel_sale(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    buyer: HumanAddr,
-    id: u64,
+    b_id: u64,
 ) -> Result<HandleResponse, MyCustomError> {
-    // convert human_addr to canonical
-    let seller_addr = &deps.api.canonical_address(&info.sender)?;
+    let seller_addr = deps.api.canonical_address(&info.sender)?;
+    let offer = sale_offers_store_read(deps.storage)
+        .may_load(&b_id.to_be_bytes())?
+        .filter(|offer| offer.seller == seller_addr)
+        .ok_or(MyCustomError::NoSaleOffer { b_id })?;
+    sale_offers_store(deps.storage).remove(&b_id.to_be_bytes());
+    reindex_bonsai(deps.storage, b_id)?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::CANCEL_SALE, &env)
+        .bonsai_id(b_id)
+        .from(&info.sender)
+        .to(&deps.api.human_address(&offer.recipient)?)
+        .attributes();
+
+    Ok(res)
+}
+
+/// return the balance of the given address in the same asset of the price
+fn query_price_balance(
+    querier: &QuerierWrapper,
+    address: &HumanAddr,
+    price: &Price,
+) -> StdResult<Price> {
+    match price {
+        Price::Native(coin) => Ok(querier.query_balance(address, &coin.denom)?.into()),
+        Price::Cw20(coin) => {
+            let request = QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: coin.address.clone(),
+                msg: to_binary(&Cw20QueryMsg::Balance {
+                    address: address.clone(),
+                })?,
+            });
+            let res: BalanceResponse = querier.query(&request)?;
+            Ok(Price::Cw20(Cw20CoinHuman {
+                address: coin.address.clone(),
+                amount: res.balance,
+            }))
+        }
+    }
+}
+
+/// the traits of a bonsai, after the standard attributes
+fn trait_attributes(traits: &Traits) -> Vec<Attribute> {
+    vec![
+        attr("species", format!("{:?}", traits.species).to_lowercase()),
+        attr("rarity", format!("{:?}", traits.rarity).to_lowercase()),
+    ]
+}
 
-    // extract the bonsai to sell
-    let bonsai_to_sell = gardeners_store(deps.storage)
-        .load(seller_addr.as_slice())?
+/// the traits and the generation of an owned bonsai that can breed at the given height:
+/// mature, off cooldown and with revealed traits
+fn check_breedable(
+    storage: &dyn Storage,
+    gardener: &Gardener,
+    id: u64,
+    height: u64,
+) -> Result<(Traits, u64), MyCustomError> {
+    let bonsai = gardener
         .bonsais
         .iter()
         .find(|bonsai| bonsai.id == id)
-        .clone()
-        .ok_or_else(|| StdError::generic_err(format!("No bonsai with {} id found", id)))?
-        .clone();
+        .ok_or(MyCustomError::NotBonsaiOwner { b_id: id })?;
+    if height.saturating_sub(bonsai.birth_date) < MATURITY_BLOCKS {
+        return Err(MyCustomError::NotMature { b_id: id });
+    }
+    if let Some(until) = cooldowns_store_read(storage).may_load(&id.to_be_bytes())? {
+        if height < until {
+            return Err(MyCustomError::OnCooldown { b_id: id, until });
+        }
+    }
+    let traits = bonsai
+        .traits
+        .ok_or(MyCustomError::TraitsNotRevealed { b_id: id })?;
+    let generation = lineage_store_read(storage)
+        .may_load(&id.to_be_bytes())?
+        .map_or(0, |lineage| lineage.generation);
+    Ok((traits, generation))
+}
 
-    // check buyer's funds
-    let denom = &deps.querier.query_bonded_denom()?;
-    let balance = deps.querier.query_balance(&buyer, &denom.as_str())?;
+/// grow a young bonsai with the given traits in the garden, numbered after the whole
+/// inventory. Its parents rest for a while before breeding again
+fn grow_offspring(
+    storage: &mut dyn Storage,
+    height: u64,
+    gardener: &mut Gardener,
+    traits: Traits,
+    parents: Vec<u64>,
+    generation: u64,
+) -> StdResult<u64> {
+    let config = config_store_read(storage).load()?;
+    let id = mint_ids(storage, 1)?;
+    let mut bonsai = Bonsai::new(id, height, config.default_prices);
+    bonsai.royalty = config.royalty;
+    bonsai.traits = Some(traits);
+    index_bonsai(storage, &bonsai, Some(&gardener.address))?;
+    gardener.bonsais.push(bonsai);
 
-    if balance.amount < bonsai_to_sell.price.amount {
-        return Err(MyCustomError::Std(StdError::generic_err(
-            "Insufficient buyers funds",
-        )));
+    for parent in parents.iter() {
+        cooldowns_store(storage).save(&parent.to_be_bytes(), &(height + CROSS_COOLDOWN_BLOCKS))?;
     }
+    lineage_store(storage).save(
+        &id.to_be_bytes(),
+        &Lineage {
+            parents,
+            generation: generation + 1,
+        },
+    )?;
+    Ok(id)
+}
 
-    // add sold bonsai to buyer list
-    let buyer_addr = &deps.api.canonical_address(&buyer)?;
-    gardeners_store(deps.storage).update::<_, StdError>(buyer_addr.as_slice(), |gardener| {
-        let mut unwrapped = gardener.unwrap();
-        unwrapped.bonsais.push(bonsai_to_sell);
-        Ok(unwrapped)
-    })?;
+/// breed a child from two mature bonsais of the sender, off cooldown and with revealed traits
+pub fn handle_cross_bonsais(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent_a: u64,
+    parent_b: u64,
+) -> Result<HandleResponse, MyCustomError> {
+    if parent_a == parent_b {
+        return Err(MyCustomError::DuplicatedBonsai { b_id: parent_a });
+    }
+    let owner_addr = deps.api.canonical_address(&info.sender)?;
+    let mut gardener = gardeners_store_read(deps.storage)
+        .may_load(owner_addr.as_slice())?
+        .ok_or(MyCustomError::GardenerNotFound {})?;
 
-    // remove the sold bonsai from seller list
-    remove_bonsai(deps, seller_addr.clone(), id);
+    let height = env.block.height;
+    let (traits_a, generation_a) = check_breedable(deps.storage, &gardener, parent_a, height)?;
+    let (traits_b, generation_b) = check_breedable(deps.storage, &gardener, parent_b, height)?;
+
+    let mut prng = Prng::new(&[
+        &height.to_be_bytes(),
+        &env.block.time.to_be_bytes(),
+        &env.block.time_nanos.to_be_bytes(),
+        owner_addr.as_slice(),
+        &parent_a.to_be_bytes(),
+        &parent_b.to_be_bytes(),
+    ]);
+    let traits = Traits::cross(&traits_a, &traits_b, &mut prng);
+    let id = grow_offspring(
+        deps.storage,
+        height,
+        &mut gardener,
+        traits,
+        vec![parent_a, parent_b],
+        generation_a.max(generation_b),
+    )?;
+    gardeners_store(deps.storage).save(owner_addr.as_slice(), &gardener)?;
+    update_scores(deps.storage, &owner_addr, None)?;
 
     let mut res = HandleResponse::default();
-    res.attributes = vec![
-        attr("action", "sell_bonsai"),
-        attr("from", info.sender),
-        attr("to", buyer),
-    ];
+    res.attributes = Event::new(events::CROSS_BONSAIS, &env)
+        .bonsai_id(id)
+        .to(&info.sender)
+        .attributes();
+    res.attributes
+        .extend(vec![attr("parent_a", parent_a), attr("parent_b", parent_b)]);
+    res.attributes.extend(trait_attributes(&traits));
 
     Ok(res)
 }
 
+/// take a cutting from a mature bonsai of the sender, which is kept. The cutting grows
+/// into a clone of the same species one rarity below, and both rest for a while
+pub fn handle_propagate_bonsai(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    b_id: u64,
+) -> Result<HandleResponse, MyCustomError> {
+    let owner_addr = deps.api.canonical_address(&info.sender)?;
+    let mut gardener = gardeners_store_read(deps.storage)
+        .may_load(owner_addr.as_slice())?
+        .ok_or(MyCustomError::GardenerNotFound {})?;
+
+    let height = env.block.height;
+    let (traits, generation) = check_breedable(deps.storage, &gardener, b_id, height)?;
+    let traits = Traits {
+        species: traits.species,
+        rarity: traits.rarity.previous(),
+    };
+    let id = grow_offspring(
+        deps.storage,
+        height,
+        &mut gardener,
+        traits,
+        vec![b_id],
+        generation,
+    )?;
+    gardeners_store(deps.storage).save(owner_addr.as_slice(), &gardener)?;
+    update_scores(deps.storage, &owner_addr, None)?;
+    cooldowns_store(deps.storage).save(&id.to_be_bytes(), &(height + CROSS_COOLDOWN_BLOCKS))?;
+
+    let mut res = HandleResponse::default();
+    res.attributes = Event::new(events::PROPAGATE_BONSAI, &env)
+        .bonsai_id(b_id)
+        .to(&info.sender)
+        .attributes();
+    res.attributes.push(attr("clone_id", id));
+    res.attributes.extend(trait_attributes(&traits));
+
+    Ok(res)
+}
+
+/// burn bonsais of the sender, leaving a tombstone for each of them
 pub fn handle_cut_bonsai(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    id: u64,
+    ids: Vec<u64>,
 ) -> Result<HandleResponse, MyCustomError> {
+    check_batch(&ids)?;
+
     let owner_addr = deps.api.canonical_address(&info.sender)?;
-    remove_bonsai(deps, owner_addr, id);
+    let mut gardener = gardeners_store_read(deps.storage)
+        .may_load(owner_addr.as_slice())?
+        .ok_or(MyCustomError::GardenerNotFound {})?;
+
+    // check every bonsai before cutting any of them
+    for &id in ids.iter() {
+        if !gardener.bonsais.iter().any(|bonsai| bonsai.id == id) {
+            return Err(MyCustomError::NotBonsaiOwner { b_id: id });
+        }
+    }
+    gardener.bonsais.retain(|bonsai| !ids.contains(&bonsai.id));
+    gardeners_store(deps.storage).save(owner_addr.as_slice(), &gardener)?;
+    update_scores(deps.storage, &owner_addr, None)?;
+    burned_store(deps.storage)
+        .update(|burned| -> StdResult<u64> { Ok(burned + ids.len() as u64) })?;
 
     let mut res = HandleResponse::default();
-    res.attributes = vec![
-        attr("action", "cut_bonsai"),
-        attr("owner", info.sender),
-        attr("bonsai_id", id),
-    ];
+    for &id in ids.iter() {
+        sale_offers_store(deps.storage).remove(&id.to_be_bytes());
+        seed_commitments_store(deps.storage).remove(&id.to_be_bytes());
+        unindex_bonsai(deps.storage, id)?;
+        tombstones_store(deps.storage).save(
+            &id.to_be_bytes(),
+            &Tombstone {
+                id,
+                last_owner: owner_addr.clone(),
+                cut_height: env.block.height,
+            },
+        )?;
+        record_history(
+            deps.storage,
+            id,
+            HistoryKind::Cut,
+            Some(owner_addr.clone()),
+            None,
+            None,
+            env.block.height,
+        )?;
+        res.attributes.extend(
+            Event::new(events::CUT_BONSAI, &env)
+                .bonsai_id(id)
+                .from(&info.sender)
+                .attributes(),
+        );
+    }
 
     Ok(res)
 }
 
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
         QueryMsg::GetBonsais {} => to_binary(&query_bonsais(deps)?),
+        QueryMsg::GetBonsai { b_id } => to_binary(&query_bonsai(deps, b_id)?),
         QueryMsg::GetGardener { sender } => to_binary(&query_gardener(deps, sender)?),
         QueryMsg::GetGardeners {} => to_binary(&query_all_gardeners(deps)?),
+        QueryMsg::GetRoyaltyInfo { b_id, sale_price } => {
+            to_binary(&query_royalty_info(deps, b_id, sale_price)?)
+        }
+        QueryMsg::GetCurrentPrice { b_id } => to_binary(&query_current_price(deps, env, b_id)?),
+        QueryMsg::GetPauseStatus {} => to_binary(&query_pause_status(deps)?),
+        QueryMsg::HasRole { address, role } => to_binary(&query_has_role(deps, address, role)?),
+        QueryMsg::GetLineage { b_id } => to_binary(&query_lineage(deps, b_id)?),
+        QueryMsg::SearchBonsais {
+            filter,
+            order,
+            start_after,
+            limit,
+        } => to_binary(&query_search_bonsais(
+            deps,
+            filter,
+            order,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::TopGardeners { by, limit } => to_binary(&query_top_gardeners(deps, by, limit)?),
+        QueryMsg::GetStats {} => to_binary(&query_stats(deps)?),
+        QueryMsg::GetSupply {} => to_binary(&query_supply(deps)?),
+        QueryMsg::GetBonsaiHistory {
+            b_id,
+            start_after,
+            limit,
+        } => to_binary(&query_bonsai_history(deps, b_id, start_after, limit)?),
+        QueryMsg::GetTombstone { b_id } => to_binary(&query_tombstone(deps, b_id)?),
+        QueryMsg::GetRandomPurchase { address } => {
+            to_binary(&query_random_purchase(deps, address)?)
+        }
+        QueryMsg::GetSaleEvents {} => to_binary(&query_sale_events(deps, env)?),
+        QueryMsg::GetPresaleStatus { address } => {
+            to_binary(&query_presale_status(deps, env, address)?)
+        }
+        #[cfg(feature = "invariants")]
+        QueryMsg::CheckInvariants {} => to_binary(&query_invariants(deps)?),
     }
 }
 
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = config_store_read(deps.storage).load()?;
+    let cw20_whitelist = cw20_whitelist_store_read(deps.storage)
+        .load()?
+        .iter()
+        .map(|token| deps.api.human_address(token))
+        .collect::<StdResult<Vec<HumanAddr>>>()?;
+
+    Ok(ConfigResponse {
+        owner: deps.api.human_address(&config.owner)?,
+        default_prices: config.default_prices,
+        cw20_whitelist,
+        fee_collector: deps.api.human_address(&config.fee_collector)?,
+        protocol_fee_bps: config.protocol_fee_bps,
+        royalty_bps: config.royalty.map_or(0, |royalty| royalty.bps),
+        pricing: config.pricing,
+        presale: config.presale,
+        max_per_wallet: config.max_per_wallet,
+        max_initial_inventory: config.max_initial_inventory,
+        commit_reveal: config.commit_reveal,
+    })
+}
+
 pub fn query_bonsais(deps: Deps) -> StdResult<BonsaiList> {
     let bonsais = bonsai_store_read(deps.storage).load()?;
     Ok(bonsais)
 }
 
+/// look for the bonsai with the given id in the shop first and then in the gardens
+/// a virtual bonsai is returned as it would be grown in the current block
+pub fn query_bonsai(deps: Deps, b_id: u64) -> StdResult<Bonsai> {
+    let shop = bonsai_store_read(deps.storage).load()?;
+    if let Some(bonsai) = shop.bonsais.into_iter().find(|bonsai| bonsai.id == b_id) {
+        return Ok(bonsai);
+    }
+    if let Some(bonsai) = virtual_bonsai(deps.storage, b_id)? {
+        return Ok(bonsai);
+    }
+
+    for item in gardeners_store_read(deps.storage).range(None, None, Order::Ascending) {
+        let (_, gardener) = item?;
+        if let Some(bonsai) = gardener
+            .bonsais
+            .into_iter()
+            .find(|bonsai| bonsai.id == b_id)
+        {
+            return Ok(bonsai);
+        }
+    }
+
+    Err(StdError::not_found("Bonsai"))
+}
+
+/// the prices a buyer has to pay for the bonsai at the current block
+pub fn query_current_price(deps: Deps, env: Env, b_id: u64) -> StdResult<CurrentPriceResponse> {
+    let height = env.block.height;
+    let shop = bonsai_store_read(deps.storage).load()?;
+    let shop_bonsai = match shop.bonsais.into_iter().find(|bonsai| bonsai.id == b_id) {
+        Some(bonsai) => Some(bonsai),
+        None => virtual_bonsai(deps.storage, b_id)?,
+    };
+    let offer = sale_offers_store_read(deps.storage).may_load(&b_id.to_be_bytes())?;
+    let (prices, discount_percent) = match (shop_bonsai, offer) {
+        (Some(bonsai), _) => shop_prices(deps.storage, &bonsai, height, 0)
+            .map_err(|err| StdError::generic_err(err.to_string()))?,
+        // an offered bonsai is sold at the prices asked by its owner
+        (None, Some(offer)) => (offer.prices, 0),
+        (None, None) => (query_bonsai(deps, b_id)?.prices, 0),
+    };
+
+    Ok(CurrentPriceResponse {
+        prices,
+        discount_percent,
+    })
+}
+
+/// the scheduled sale events and the discount applied in the current block
+pub fn query_sale_events(deps: Deps, env: Env) -> StdResult<SaleEventsResponse> {
+    let events = sale_events_store_read(deps.storage).load()?;
+
+    Ok(SaleEventsResponse {
+        active_discount_percent: active_discount(&events, env.block.height),
+        events,
+    })
+}
+
+/// whether the address can already buy from the shop and how many bonsais it bought
+pub fn query_presale_status(
+    deps: Deps,
+    env: Env,
+    address: HumanAddr,
+) -> StdResult<PresaleStatusResponse> {
+    let config = config_store_read(deps.storage).load()?;
+    let canonical_addr = deps.api.canonical_address(&address)?;
+
+    Ok(PresaleStatusResponse {
+        presale_active: matches!(&config.presale, Some(presale) if presale.is_active(env.block.height)),
+        public_sale_height: config.presale.map(|presale| presale.public_sale_height),
+        allowlisted: allowlist_store_read(deps.storage)
+            .may_load(canonical_addr.as_slice())?
+            .unwrap_or(false),
+        purchased: gardeners_store_read(deps.storage)
+            .may_load(canonical_addr.as_slice())?
+            .map_or(0, |gardener| gardener.purchased),
+        max_per_wallet: config.max_per_wallet,
+    })
+}
+
+pub fn query_pause_status(deps: Deps) -> StdResult<PauseStatusResponse> {
+    let scope = paused_store_read(deps.storage).may_load()?;
+    Ok(PauseStatusResponse { scope })
+}
+
+/// the bonsais grown by the shop have no parents
+pub fn query_lineage(deps: Deps, b_id: u64) -> StdResult<Lineage> {
+    Ok(lineage_store_read(deps.storage)
+        .may_load(&b_id.to_be_bytes())?
+        .unwrap_or(Lineage {
+            parents: vec![],
+            generation: 0,
+        }))
+}
+
+/// a filter on an exact value reads the bonsais from its index and sorts them, otherwise
+/// the index of the order is read within the bounds of the filter
+pub fn query_search_bonsais(
+    deps: Deps,
+    filter: BonsaiFilter,
+    order: Option<SearchOrder>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SearchBonsaisResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = order.unwrap_or(SearchOrder::Oldest);
+    let owner = match &filter.owner {
+        Some(owner) => Some(deps.api.canonical_address(owner)?),
+        None => None,
+    };
+    let asset = match (&order, &filter.price) {
+        (SearchOrder::Cheapest, None) | (SearchOrder::Priciest, None) => {
+            return Err(StdError::generic_err(
+                "Sorting by price needs a price filter",
+            ))
+        }
+        (_, Some(price)) => Some(price.asset.as_str()),
+        _ => None,
+    };
+    let sort_asset = match order {
+        SearchOrder::Cheapest | SearchOrder::Priciest => asset,
+        SearchOrder::Oldest | SearchOrder::Newest => None,
+    };
+    let direction = match order {
+        SearchOrder::Oldest | SearchOrder::Cheapest => Order::Ascending,
+        SearchOrder::Newest | SearchOrder::Priciest => Order::Descending,
+    };
+
+    let is_match = |entry: &SearchEntry| {
+        let bonsai = &entry.bonsai;
+        if let Some(filter) = &filter.price {
+            let in_range = |price: &Price| {
+                price.asset() == filter.asset
+                    && !matches!(filter.min, Some(min) if price.amount() < min)
+                    && !matches!(filter.max, Some(max) if price.amount() > max)
+            };
+            if !entry.prices.iter().any(in_range) {
+                return false;
+            }
+        }
+        let species = bonsai.traits.map(|traits| traits.species);
+        let rarity = bonsai.traits.map(|traits| traits.rarity);
+        !matches!(filter.min_birth_date, Some(min) if bonsai.birth_date < min)
+            && !matches!(filter.max_birth_date, Some(max) if bonsai.birth_date > max)
+            && !matches!(&owner, Some(owner) if entry.owner.as_ref() != Some(owner))
+            && !matches!(filter.for_sale, Some(for_sale) if entry.for_sale != for_sale)
+            && (filter.species.is_none() || filter.species == species)
+            && (filter.rarity.is_none() || filter.rarity == rarity)
+    };
+    let load_entry = |id: u64| search_entries_store_read(deps.storage).load(&id.to_be_bytes());
+
+    // the search resumes right after the sort key of the last bonsai returned
+    let cursor = match start_after {
+        Some(id) => Some(load_entry(id)?.sort_key(sort_asset).ok_or_else(|| {
+            StdError::generic_err("The bonsai to start after has no price in the asset")
+        })?),
+        None => None,
+    };
+    let after_cursor = |key: &[u8]| match (&cursor, direction) {
+        (None, _) => true,
+        (Some(cursor), Order::Ascending) => key > cursor.as_slice(),
+        (Some(cursor), Order::Descending) => key < cursor.as_slice(),
+    };
+
+    let narrowing = owner
+        .clone()
+        .map(SearchIndex::Owner)
+        .or_else(|| filter.rarity.map(SearchIndex::Rarity))
+        .or_else(|| filter.species.map(SearchIndex::Species));
+
+    let found = match narrowing {
+        Some(index) => {
+            let mut entries = vec![];
+            for item in
+                search_index_store_read(deps.storage, &index).range(None, None, Order::Ascending)
+            {
+                let entry = load_entry(item?.1)?;
+                if let Some(key) = entry.sort_key(sort_asset) {
+                    if is_match(&entry) && after_cursor(&key) {
+                        entries.push((key, entry));
+                    }
+                }
+            }
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            if let Order::Descending = direction {
+                entries.reverse();
+            }
+            entries
+                .into_iter()
+                .take(limit)
+                .map(|(_, entry)| entry)
+                .collect()
+        }
+        None => {
+            // the bounds of the range filter on the sorted value, in the
+            // index of the bonsais with the searched for sale status if any
+            let (index, min, max) = match (sort_asset, &filter.price) {
+                (Some(asset), Some(price)) => (
+                    match filter.for_sale {
+                        Some(for_sale) => SearchIndex::PriceForSale(for_sale, asset.to_string()),
+                        None => SearchIndex::Price(asset.to_string()),
+                    },
+                    price.min.map(|min| min.u128().to_be_bytes().to_vec()),
+                    price
+                        .max
+                        .and_then(|max| max.u128().checked_add(1))
+                        .map(|end| end.to_be_bytes().to_vec()),
+                ),
+                _ => (
+                    match filter.for_sale {
+                        Some(for_sale) => SearchIndex::BirthDateForSale(for_sale),
+                        None => SearchIndex::BirthDate,
+                    },
+                    filter.min_birth_date.map(|min| min.to_be_bytes().to_vec()),
+                    filter
+                        .max_birth_date
+                        .and_then(|max| max.checked_add(1))
+                        .map(|end| end.to_be_bytes().to_vec()),
+                ),
+            };
+            let (mut start, mut end) = (min, max);
+            match (&cursor, direction) {
+                (Some(cursor), Order::Ascending) => {
+                    // the smallest key after the cursor
+                    let next = [cursor.as_slice(), &[0]].concat();
+                    start = Some(start.map_or(next.clone(), |start| start.max(next)));
+                }
+                (Some(cursor), Order::Descending) => {
+                    end = Some(end.map_or(cursor.clone(), |end| end.min(cursor.clone())));
+                }
+                (None, _) => {}
+            }
+            let mut entries = vec![];
+            for item in search_index_store_read(deps.storage, &index).range(
+                start.as_deref(),
+                end.as_deref(),
+                direction,
+            ) {
+                let entry = load_entry(item?.1)?;
+                if is_match(&entry) {
+                    entries.push(entry);
+                    if entries.len() == limit {
+                        break;
+                    }
+                }
+            }
+            entries
+        }
+    };
+
+    let bonsais = found
+        .into_iter()
+        .map(|entry| {
+            Ok(FoundBonsai {
+                owner: match &entry.owner {
+                    Some(owner) => Some(deps.api.human_address(owner)?),
+                    None => None,
+                },
+                bonsai: entry.bonsai,
+                for_sale: entry.for_sale,
+                prices: entry.prices,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SearchBonsaisResponse { bonsais })
+}
+
+/// read from the leaderboard index, without going through every gardener
+pub fn query_top_gardeners(
+    deps: Deps,
+    by: Leaderboard,
+    limit: Option<u32>,
+) -> StdResult<TopGardenersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = match by {
+        Leaderboard::OldestTree => Order::Ascending,
+        _ => Order::Descending,
+    };
+    let gardeners = ranking_store_read(deps.storage, &by)
+        .range(None, None, order)
+        .take(limit)
+        .map(|item| {
+            let (_, address) = item?;
+            Ok(RankedGardener {
+                scores: scores_store_read(deps.storage).load(address.as_slice())?,
+                address: deps.api.human_address(&address)?,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TopGardenersResponse { gardeners })
+}
+
+pub fn query_stats(deps: Deps) -> StdResult<Stats> {
+    stats_store_read(deps.storage).load()
+}
+
+/// the bonsais in the shop were minted but aren't owned yet
+pub fn query_supply(deps: Deps) -> StdResult<SupplyResponse> {
+    let inventory = inventory_store_read(deps.storage).load()?;
+    let burned = burned_store_read(deps.storage).load()?;
+    let in_shop = bonsai_store_read(deps.storage).load()?.bonsais.len() as u64;
+    Ok(SupplyResponse {
+        total_supply: inventory.total_supply,
+        minted: inventory.minted,
+        sold: sold_store_read(deps.storage).load()?,
+        owned: inventory.minted.saturating_sub(in_shop + burned),
+        burned,
+    })
+}
+
+/// entries returned by a page when no limit is given, and at most
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn query_bonsai_history(
+    deps: Deps,
+    b_id: u64,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<BonsaiHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|index| index.saturating_add(1).to_be_bytes());
+    let entries = history_store_read(deps.storage, b_id)
+        .range(start.as_ref().map(|s| &s[..]), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(BonsaiHistoryResponse { entries })
+}
+
+pub fn query_tombstone(deps: Deps, b_id: u64) -> StdResult<Option<Tombstone>> {
+    tombstones_store_read(deps.storage).may_load(&b_id.to_be_bytes())
+}
+
+pub fn query_random_purchase(deps: Deps, address: HumanAddr) -> StdResult<Option<RandomPurchase>> {
+    let canonical_addr = deps.api.canonical_address(&address)?;
+    random_purchases_store_read(deps.storage).may_load(canonical_addr.as_slice())
+}
+
+pub fn query_has_role(deps: Deps, address: HumanAddr, role: Role) -> StdResult<HasRoleResponse> {
+    Ok(HasRoleResponse {
+        has_role: has_role(deps, &address, role)?,
+    })
+}
+
 pub fn query_gardener(deps: Deps, sender: HumanAddr) -> StdResult<Option<Gardener>> {
     let canonical_addr = deps.api.canonical_address(&sender)?;
     let response = gardeners_store_read(deps.storage).may_load(canonical_addr.as_slice())?;
@@ -234,3 +2412,41 @@ pub fn query_all_gardeners(deps: Deps) -> StdResult<AllGardenersResponse> {
 
     Ok(AllGardenersResponse { gardeners: res? })
 }
+
+/// split a resale price of the bonsai between fee collector, creator and seller
+pub fn query_royalty_info(
+    deps: Deps,
+    b_id: u64,
+    sale_price: Uint128,
+) -> StdResult<RoyaltyInfoResponse> {
+    let bonsai = query_bonsai(deps, b_id)?;
+    let config = config_store_read(deps.storage).load()?;
+
+    let protocol_fee = config.protocol_fee(sale_price);
+    let (royalty_recipient, royalty) = match bonsai.royalty {
+        Some(royalty) => (
+            Some(deps.api.human_address(&royalty.recipient)?),
+            royalty.amount(sale_price),
+        ),
+        None => (None, Uint128::zero()),
+    };
+
+    Ok(RoyaltyInfoResponse {
+        fee_collector: deps.api.human_address(&config.fee_collector)?,
+        protocol_fee,
+        royalty_recipient,
+        royalty,
+        seller_amount: ((sale_price - protocol_fee)? - royalty)?,
+    })
+}
+
+/// check that every bonsai has exactly one owner (shop included), only for local testing
+#[cfg(any(test, feature = "invariants"))]
+pub fn query_invariants(deps: Deps) -> StdResult<InvariantsResponse> {
+    let duplicated = duplicated_bonsais(deps.storage)?;
+
+    Ok(InvariantsResponse {
+        valid: duplicated.is_empty(),
+        duplicated,
+    })
+}
@@ -1,13 +1,29 @@
-use crate::contract::{handle, init, query_all_gardeners, query_bonsais, query_gardener};
-use crate::msg::{HandleMsg, InitMsg};
-use crate::state::{bonsai_store_read, gardeners_store, Bonsai, Gardener};
+use crate::contract::{
+    handle, init, query_all_gardeners, query_bonsai, query_bonsai_history, query_bonsais,
+    query_config, query_current_price, query_gardener, query_has_role, query_invariants,
+    query_lineage, query_pause_status, query_presale_status, query_random_purchase,
+    query_royalty_info, query_search_bonsais, query_stats, query_supply, query_tombstone,
+    query_top_gardeners,
+};
+use crate::errors::MyCustomError;
+use crate::msg::{
+    BonsaiFilter, ConfigResponse, HandleMsg, InitMsg, PriceFilter, ReceiveMsg, RoyaltyInfoResponse,
+    SearchOrder, SupplyResponse,
+};
+use crate::state::{
+    bonsai_store_read, gardeners_store, inventory_store_read, Bonsai, Gardener, HistoryKind,
+    Leaderboard, PauseScope, Presale, Price, PricingMode, Rarity, Role, MAX_INITIAL_INVENTORY,
+    MAX_STEP_BPS, REVEAL_DEADLINE_BLOCKS,
+};
 use assert::equal;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockQuerier};
 use cosmwasm_std::{
-    attr, coin, coins, Api, Coin, Decimal, Deps, DepsMut, Env, HandleResponse, HumanAddr,
-    MessageInfo, Validator,
+    attr, coin, coins, to_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    HandleResponse, HumanAddr, MessageInfo, Uint128, Validator, WasmMsg,
 };
+use cw20::{Cw20CoinHuman, Cw20HandleMsg, Cw20ReceiveMsg};
 use rand::seq::SliceRandom;
+use sha2::{Digest, Sha256};
 
 const DEFAULT_VALIDATOR: &str = "default-validator";
 const BOND_DENOM: &str = "bonsai";
@@ -42,8 +58,18 @@ fn set_balance(querier: &mut MockQuerier, addr: HumanAddr, balance: Vec<Coin>) {
 // this will set up the init for other tests
 fn setup_test(deps: DepsMut, env: &Env, info: MessageInfo, bonsai_price: Coin, bonsai_number: u64) {
     let init_msg = InitMsg {
-        price: bonsai_price,
+        owner: None,
+        prices: vec![bonsai_price.into()],
         number: bonsai_number,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
     };
     init(deps, env.clone(), info, init_msg).unwrap();
 }
@@ -62,15 +88,30 @@ fn test_init() {
 
     // Init an empty contract
     let init_msg = InitMsg {
-        price: coin(20, "bonsai"),
+        owner: None,
+        prices: vec![coin(20, "bonsai").into()],
         number: 20,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
     };
     let sender_addr = HumanAddr::from("addr0001");
     let env = mock_env_height(100);
     let res = init(deps.as_mut(), env, mock_info(&sender_addr, &[]), init_msg).unwrap();
     assert_eq!(0, res.messages.len());
 
-    let exp_log = vec![attr("action", "grown_bonsais")];
+    let exp_log = vec![
+        attr("action", "grown_bonsais"),
+        attr("from", &sender_addr),
+        attr("height", 100),
+        attr("number", 20),
+    ];
 
     assert_eq!(res.attributes, exp_log);
 
@@ -93,7 +134,8 @@ fn test_become_gardener_works() {
     let mut exp_res = HandleResponse::default();
     exp_res.attributes = vec![
         attr("action", "become_gardener"),
-        attr("gardener_addr", &sender_addr),
+        attr("from", &sender_addr),
+        attr("height", bonsai_height),
     ];
 
     let msg = HandleMsg::BecomeGardener {
@@ -136,9 +178,21 @@ fn test_buy_bonsai_works() {
 
     exp_res.attributes = vec![
         attr("action", "buy_bonsai"),
-        attr("buyer", &info.sender),
-        attr("amount", bonsai_price.amount),
+        attr("bonsai_id", bonsai_id),
+        attr("from", &env.contract.address),
+        attr("to", &info.sender),
+        attr("price", bonsai_price.amount),
+        attr("denom", BOND_DENOM),
+        attr("height", bonsai_height),
+        attr("protocol_fee", 0u128),
+        attr("refund", 15u128),
     ];
+    // the funds sent over the price are given back
+    exp_res.messages = vec![CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address.clone(),
+        to_address: info.sender.clone(),
+        amount: coins(15, BOND_DENOM),
+    })];
 
     let msg = HandleMsg::BuyBonsai { b_id: bonsai_id };
 
@@ -148,6 +202,214 @@ fn test_buy_bonsai_works() {
     assert_eq!(exp_res, res.unwrap())
 }
 
+#[test]
+fn test_buy_bonsai_with_any_accepted_price() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let env = mock_env_height(100);
+    let info = mock_info(sender_addr.clone(), &[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into(), coin(5, "ustake").into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let bonsai_id = get_random_bonsai_id(deps.as_ref());
+    let msg = HandleMsg::BuyBonsai { b_id: bonsai_id };
+
+    // not enough of any accepted denom
+    let info = mock_info(
+        sender_addr.clone(),
+        &[coin(9, BOND_DENOM), coin(4, "ustake")],
+    );
+    let res = handle(deps.as_mut(), env.clone(), info, msg.clone());
+    assert!(res.is_err());
+
+    // the second accepted price is covered even if it's not the first coin sent
+    let info = mock_info(
+        sender_addr.clone(),
+        &[coin(100, "ucosm"), coin(5, "ustake")],
+    );
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        vec![
+            attr("action", "buy_bonsai"),
+            attr("bonsai_id", bonsai_id),
+            attr("from", &env.contract.address),
+            attr("to", &sender_addr),
+            attr("price", 5u128),
+            attr("denom", "ustake"),
+            attr("height", env.block.height),
+            attr("protocol_fee", 0u128),
+            attr("refund", 100u128),
+        ],
+        res.attributes
+    );
+    assert_eq!(
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: sender_addr.clone(),
+            amount: coins(100, "ucosm"),
+        })],
+        res.messages
+    );
+
+    let bonsai = query_bonsai(deps.as_ref(), bonsai_id).unwrap();
+    assert_eq!(
+        vec![
+            Price::from(coin(10, BOND_DENOM)),
+            Price::from(coin(5, "ustake"))
+        ],
+        bonsai.prices
+    );
+
+    let gardener = query_gardener(deps.as_ref(), sender_addr).unwrap().unwrap();
+    assert_eq!(vec![bonsai], gardener.bonsais)
+}
+
+#[test]
+fn test_buy_bonsai_with_cw20_works() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let token_addr = HumanAddr::from("token0001");
+    let env = mock_env_height(100);
+    let info = mock_info(sender_addr.clone(), &[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![
+            coin(10, BOND_DENOM).into(),
+            Price::Cw20(Cw20CoinHuman {
+                address: token_addr.clone(),
+                amount: Uint128(50),
+            }),
+        ],
+        number: 10,
+        cw20_whitelist: vec![token_addr.clone()],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let bonsai_id = get_random_bonsai_id(deps.as_ref());
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: sender_addr.clone(),
+        amount: Uint128(50),
+        msg: Some(to_binary(&ReceiveMsg::BuyBonsai { b_id: bonsai_id }).unwrap()),
+    });
+
+    // tokens coming from a contract that isn't whitelisted are refused
+    let other_token = mock_info(HumanAddr::from("token0002"), &[]);
+    let res = handle(deps.as_mut(), env.clone(), other_token, msg.clone());
+    match res {
+        Err(MyCustomError::TokenNotWhitelisted { token }) => {
+            assert_eq!(HumanAddr::from("token0002"), token)
+        }
+        _ => panic!("expected TokenNotWhitelisted error, got {:?}", res),
+    }
+
+    let token_info = mock_info(token_addr.clone(), &[]);
+    let res = handle(deps.as_mut(), env.clone(), token_info, msg).unwrap();
+    assert_eq!(
+        vec![
+            attr("action", "buy_bonsai"),
+            attr("bonsai_id", bonsai_id),
+            attr("from", &env.contract.address),
+            attr("to", &sender_addr),
+            attr("price", 50u128),
+            attr("denom", &token_addr),
+            attr("height", env.block.height),
+            attr("protocol_fee", 0u128),
+        ],
+        res.attributes
+    );
+    assert!(res.messages.is_empty());
+
+    let gardener = query_gardener(deps.as_ref(), sender_addr.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(bonsai_id, gardener.bonsais[0].id);
+
+    // the tokens sent over the price are transferred back
+    let b_id = get_random_bonsai_id(deps.as_ref());
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: sender_addr.clone(),
+        amount: Uint128(80),
+        msg: Some(to_binary(&ReceiveMsg::BuyBonsai { b_id }).unwrap()),
+    });
+    let res = handle(deps.as_mut(), env, mock_info(token_addr.clone(), &[]), msg).unwrap();
+    assert_eq!(
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token_addr,
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: sender_addr,
+                amount: Uint128(30),
+            })
+            .unwrap(),
+            send: vec![],
+        })],
+        res.messages
+    );
+}
+
+#[test]
+fn test_init_with_unlisted_cw20_price_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![Price::Cw20(Cw20CoinHuman {
+            address: HumanAddr::from("token0001"),
+            amount: Uint128(50),
+        })],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("addr0001"), &[]);
+    let res = init(deps.as_mut(), mock_env_height(100), info, init_msg);
+    match res {
+        Err(MyCustomError::TokenNotWhitelisted { .. }) => {}
+        _ => panic!("expected TokenNotWhitelisted error, got {:?}", res),
+    }
+}
+
 #[test]
 fn test_sell_bonsai_works() {
     let mut deps = mock_dependencies(&[]);
@@ -168,12 +430,8 @@ fn test_sell_bonsai_works() {
         vec![coin(1000, BOND_DENOM)],
     );
 
-    let bonsai = query_bonsais(deps.as_ref())
-        .unwrap()
-        .bonsais
-        .first()
-        .unwrap()
-        .clone();
+    // a bonsai that is no longer in the shop
+    let bonsai = Bonsai::new(10, 100, vec![bonsai_price.clone().into()]);
 
     let canonical_addr = &deps.api.canonical_address(&info.sender).unwrap();
     let gardener = Gardener::new(
@@ -193,67 +451,2723 @@ fn test_sell_bonsai_works() {
     let msg = HandleMsg::SellBonsai {
         recipient: buyer_addr.clone(),
         b_id: bonsai.clone().id,
+        prices: vec![bonsai_price.clone().into()],
     };
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
 
     let mut exp_res = HandleResponse::default();
     exp_res.attributes = vec![
         attr("action", "sell_bonsai"),
+        attr("bonsai_id", bonsai.id),
         attr("from", info.sender.clone()),
         attr("to", buyer_addr.clone()),
+        attr("price", 10u128),
+        attr("denom", BOND_DENOM),
+        attr("height", bonsai_height),
     ];
 
     assert_eq!(exp_res, res.unwrap());
 
+    // the seller keeps the bonsai until the buyer pays for it
     let gardener = query_gardener(deps.as_ref(), info.sender.clone())
         .unwrap()
         .unwrap();
-    assert_eq!(0, gardener.bonsais.len())
+    assert_eq!(1, gardener.bonsais.len());
+
+    let msg = HandleMsg::BuyBonsai { b_id: bonsai.id };
+    let buyer_info = mock_info(buyer_addr.clone(), &coins(10, BOND_DENOM));
+    let res = handle(deps.as_mut(), env.clone(), buyer_info, msg).unwrap();
+    assert_eq!(
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: sender_addr.clone(),
+            amount: coins(10, BOND_DENOM),
+        })],
+        res.messages
+    );
+
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(0, gardener.bonsais.len());
+
+    let buyer = query_gardener(deps.as_ref(), buyer_addr).unwrap().unwrap();
+    assert_eq!(vec![bonsai], buyer.bonsais)
 }
 
 #[test]
-fn test_cut_bonsai_works() {
+fn test_bonsai_history_and_stats_are_recorded() {
+    let mut deps = mock_dependencies(&[]);
+
+    let seller_addr = HumanAddr::from("addr0001");
+    let buyer_addr = HumanAddr::from("addr0002");
+    let env = mock_env_height(100);
+    let info = mock_info(seller_addr.clone(), &coins(10, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 10);
+    set_balance(
+        &mut deps.querier,
+        buyer_addr.clone(),
+        vec![coin(1000, BOND_DENOM)],
+    );
+    let buyer_info = mock_info(buyer_addr.clone(), &coins(10, BOND_DENOM));
+    for (info, name) in &[(&info, "leo"), (&buyer_info, "ricky")] {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), (*info).clone(), msg).unwrap();
+    }
+
+    handle(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        HandleMsg::BuyBonsai { b_id: 0 },
+    )
+    .unwrap();
+    let msg = HandleMsg::SellBonsai {
+        recipient: buyer_addr.clone(),
+        b_id: 0,
+        prices: vec![coin(10, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), mock_env_height(110), info, msg).unwrap();
+    let msg = HandleMsg::BuyBonsai { b_id: 0 };
+    handle(deps.as_mut(), mock_env_height(120), buyer_info.clone(), msg).unwrap();
+    let msg = HandleMsg::CutBonsai {
+        b_id: 0,
+        propagate: false,
+    };
+    handle(deps.as_mut(), mock_env_height(130), buyer_info, msg).unwrap();
+
+    let seller = deps.api.canonical_address(&seller_addr).unwrap();
+    let buyer = deps.api.canonical_address(&buyer_addr).unwrap();
+    let history = query_bonsai_history(deps.as_ref(), 0, None, None)
+        .unwrap()
+        .entries;
+    let summary: Vec<_> = history
+        .iter()
+        .map(|entry| {
+            (
+                entry.index,
+                entry.kind,
+                entry.from.clone(),
+                entry.to.clone(),
+                entry.height,
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            (0, HistoryKind::Buy, None, Some(seller.clone()), 100),
+            (
+                1,
+                HistoryKind::Sell,
+                Some(seller.clone()),
+                Some(buyer.clone()),
+                110
+            ),
+            (
+                2,
+                HistoryKind::Transfer,
+                Some(seller),
+                Some(buyer.clone()),
+                120
+            ),
+            (3, HistoryKind::Cut, Some(buyer), None, 130),
+        ],
+        summary
+    );
+    assert_eq!(Some(coin(10, BOND_DENOM).into()), history[2].price);
+
+    // the purchase from the shop and the resale
+    let stats = query_stats(deps.as_ref()).unwrap();
+    assert_eq!((2, 2), (stats.sales, stats.gardeners));
+    assert_eq!(vec![Price::from(coin(20, BOND_DENOM))], stats.volume);
+    assert_eq!(Some(coin(10, BOND_DENOM).into()), stats.last_sale);
+    assert_eq!(None, history[1].price);
+
+    let page = query_bonsai_history(deps.as_ref(), 0, Some(1), Some(2))
+        .unwrap()
+        .entries;
+    assert_eq!(history[2..4].to_vec(), page);
+    assert!(query_bonsai_history(deps.as_ref(), 1, None, None)
+        .unwrap()
+        .entries
+        .is_empty());
+}
+
+#[test]
+fn test_top_gardeners_follow_purchases_and_cuts() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let leo = mock_info(HumanAddr::from("addr0001"), &coins(20, BOND_DENOM));
+    let ricky = mock_info(HumanAddr::from("addr0002"), &coins(10, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, leo.clone(), coin(10, BOND_DENOM), 10);
+    for (info, name) in &[(&leo, "leo"), (&ricky, "ricky")] {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), (*info).clone(), msg).unwrap();
+    }
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![0, 1] };
+    handle(deps.as_mut(), env.clone(), leo.clone(), msg).unwrap();
+    let msg = HandleMsg::BuyBonsai { b_id: 2 };
+    handle(deps.as_mut(), env.clone(), ricky.clone(), msg).unwrap();
+
+    let top = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, by| {
+        query_top_gardeners(deps.as_ref(), by, None)
+            .unwrap()
+            .gardeners
+            .into_iter()
+            .map(|gardener| gardener.address)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec![leo.sender.clone(), ricky.sender.clone()],
+        top(&deps, Leaderboard::BonsaiCount)
+    );
+    let spent = Leaderboard::TotalSpent {
+        asset: BOND_DENOM.to_string(),
+    };
+    assert_eq!(
+        vec![leo.sender.clone(), ricky.sender.clone()],
+        top(&deps, spent.clone())
+    );
+
+    // cutting moves the gardener down but keeps what it spent
+    let msg = HandleMsg::CutBonsais { b_ids: vec![0, 1] };
+    handle(deps.as_mut(), env, leo.clone(), msg).unwrap();
+    assert_eq!(
+        vec![ricky.sender.clone(), leo.sender.clone()],
+        top(&deps, Leaderboard::BonsaiCount)
+    );
+    assert_eq!(
+        vec![leo.sender.clone(), ricky.sender.clone()],
+        top(&deps, spent)
+    );
+    assert_eq!(
+        vec![ricky.sender.clone()],
+        top(&deps, Leaderboard::OldestTree)
+    );
+    let res = query_top_gardeners(deps.as_ref(), Leaderboard::BonsaiCount, Some(1)).unwrap();
+    assert_eq!(1, res.gardeners.len());
+    assert_eq!(
+        vec![Price::from(coin(20, BOND_DENOM))],
+        query_top_gardeners(deps.as_ref(), Leaderboard::BonsaiCount, None)
+            .unwrap()
+            .gardeners[1]
+            .scores
+            .spent
+    );
+}
+
+#[test]
+fn test_search_bonsais() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(10, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 5);
+    let msg = HandleMsg::GrowBonsais {
+        number: 2,
+        prices: Some(vec![coin(30, BOND_DENOM).into()]),
+    };
+    handle(deps.as_mut(), mock_env_height(150), info.clone(), msg).unwrap();
+    let msg = HandleMsg::SetBonsaiPrice {
+        b_id: 1,
+        prices: vec![coin(5, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    handle(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        HandleMsg::BuyBonsai { b_id: 0 },
+    )
+    .unwrap();
+
+    let search = |deps: &cosmwasm_std::OwnedDeps<_, _, _>,
+                  filter: BonsaiFilter,
+                  order,
+                  start_after,
+                  limit| {
+        query_search_bonsais(deps.as_ref(), filter, order, start_after, limit)
+            .unwrap()
+            .bonsais
+            .into_iter()
+            .map(|found| found.bonsai.id)
+            .collect::<Vec<_>>()
+    };
+    let all = BonsaiFilter::default();
+    assert_eq!(
+        vec![0, 1, 2],
+        search(&deps, all.clone(), None, None, Some(3))
+    );
+    assert_eq!(
+        vec![3, 4, 5],
+        search(&deps, all.clone(), None, Some(2), Some(3))
+    );
+    assert_eq!(
+        vec![6, 5],
+        search(&deps, all.clone(), Some(SearchOrder::Newest), None, Some(2))
+    );
+    let born_later = BonsaiFilter {
+        min_birth_date: Some(120),
+        ..BonsaiFilter::default()
+    };
+    assert_eq!(vec![5, 6], search(&deps, born_later, None, None, None));
+
+    let price = |min: Option<u128>, max: Option<u128>| BonsaiFilter {
+        price: Some(PriceFilter {
+            asset: BOND_DENOM.to_string(),
+            min: min.map(Uint128),
+            max: max.map(Uint128),
+        }),
+        ..BonsaiFilter::default()
+    };
+    // the owned bonsai isn't offered, so it has no price
+    let cheapest = Some(SearchOrder::Cheapest);
+    assert_eq!(
+        vec![1, 2, 3, 4],
+        search(&deps, price(None, Some(10)), cheapest.clone(), None, None)
+    );
+    assert_eq!(
+        vec![2, 3],
+        search(
+            &deps,
+            price(None, Some(10)),
+            cheapest.clone(),
+            Some(1),
+            Some(2)
+        )
+    );
+    assert_eq!(
+        vec![6, 5, 4],
+        search(
+            &deps,
+            price(Some(10), None),
+            Some(SearchOrder::Priciest),
+            None,
+            Some(3)
+        )
+    );
+    let res = query_search_bonsais(deps.as_ref(), all.clone(), cheapest.clone(), None, None);
+    assert!(res.is_err());
+
+    // a filter on an exact value reads its own index
+    let in_shop = BonsaiFilter {
+        for_sale: Some(true),
+        ..price(None, Some(10))
+    };
+    assert_eq!(
+        vec![1, 2, 3, 4],
+        search(&deps, in_shop, cheapest, None, None)
+    );
+    let owned = BonsaiFilter {
+        owner: Some(info.sender.clone()),
+        ..BonsaiFilter::default()
+    };
+    let res = query_search_bonsais(deps.as_ref(), owned.clone(), None, None, None).unwrap();
+    assert_eq!(1, res.bonsais.len());
+    assert_eq!(
+        (Some(info.sender.clone()), false),
+        (res.bonsais[0].owner.clone(), res.bonsais[0].for_sale)
+    );
+    assert!(res.bonsais[0].prices.is_empty());
+    let not_for_sale = BonsaiFilter {
+        for_sale: Some(false),
+        ..BonsaiFilter::default()
+    };
+    assert_eq!(
+        vec![0],
+        search(&deps, not_for_sale.clone(), None, None, None)
+    );
+
+    // an offered bonsai is found at its asking price
+    let buyer = HumanAddr::from("addr0002");
+    set_balance(&mut deps.querier, buyer.clone(), coins(100, BOND_DENOM));
+    let msg = HandleMsg::BecomeGardener {
+        name: "ricky".to_string(),
+    };
+    handle(
+        deps.as_mut(),
+        mock_env_height(100),
+        mock_info(&buyer, &[]),
+        msg,
+    )
+    .unwrap();
+    let msg = HandleMsg::SellBonsai {
+        recipient: buyer,
+        b_id: 0,
+        prices: vec![coin(25, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), mock_env_height(100), info.clone(), msg).unwrap();
+    let for_sale = BonsaiFilter {
+        for_sale: Some(true),
+        ..price(Some(20), None)
+    };
+    assert_eq!(
+        vec![0, 5, 6],
+        search(&deps, for_sale, Some(SearchOrder::Cheapest), None, None)
+    );
+    assert!(search(&deps, not_for_sale, None, None, None).is_empty());
+
+    let traits = query_bonsai(deps.as_ref(), 3).unwrap().traits.unwrap();
+    let same_species = BonsaiFilter {
+        species: Some(traits.species),
+        ..BonsaiFilter::default()
+    };
+    let expected: Vec<u64> = (0..7)
+        .filter(|&id| {
+            query_bonsai(deps.as_ref(), id)
+                .unwrap()
+                .traits
+                .map(|traits| traits.species)
+                == Some(traits.species)
+        })
+        .collect();
+    assert_eq!(expected, search(&deps, same_species, None, None, None));
+
+    // cut bonsais are no longer found
+    let msg = HandleMsg::CutBonsai {
+        b_id: 0,
+        propagate: false,
+    };
+    handle(deps.as_mut(), mock_env_height(100), info, msg).unwrap();
+    assert!(search(&deps, owned, None, None, None).is_empty());
+}
+
+#[test]
+fn test_sell_bonsai_uses_price_denom() {
     let mut deps = mock_dependencies(&[]);
 
     let sender_addr = HumanAddr::from("addr0001");
-    let bonsai_price = coin(10, BOND_DENOM);
-    let bonsai_height = 100;
-    let env = mock_env_height(bonsai_height);
-    let info = mock_info(sender_addr.clone(), &coins(25, BOND_DENOM));
+    let buyer_addr = HumanAddr::from("addr0002");
+    // the price denom is different from the staking one
+    let bonsai_price = coin(10, "ucosm");
+    let env = mock_env_height(100);
+    let info = mock_info(sender_addr.clone(), &[]);
 
     // setup test environment
     setup_test(deps.as_mut(), &env, info.clone(), bonsai_price.clone(), 10);
+    set_validator(&mut deps.querier, BOND_DENOM);
+    set_balance(
+        &mut deps.querier,
+        buyer_addr.clone(),
+        vec![coin(1000, BOND_DENOM), coin(5, "ucosm")],
+    );
 
-    let canonical_addr = &deps.api.canonical_address(&info.sender.clone()).unwrap();
-    let bonsai = Bonsai::new(10, bonsai_height, bonsai_price);
+    // a bonsai that is no longer in the shop
+    let bonsai = Bonsai::new(10, 100, vec![bonsai_price.clone().into()]);
+
+    let canonical_addr = &deps.api.canonical_address(&sender_addr).unwrap();
     let gardener = Gardener::new(
         "leo".to_string(),
         canonical_addr.clone(),
         vec![bonsai.clone()],
     );
-
     let _ = gardeners_store(&mut deps.storage).save(canonical_addr.as_slice(), &gardener);
 
-    let msg = HandleMsg::CutBonsai {
-        b_id: bonsai.id.clone(),
+    let canonical_buyer_addr = &deps.api.canonical_address(&buyer_addr).unwrap();
+    let buyer = Gardener::new("ricky".to_string(), canonical_buyer_addr.clone(), vec![]);
+    let _ = gardeners_store(&mut deps.storage).save(canonical_buyer_addr.as_slice(), &buyer);
+
+    let msg = HandleMsg::SellBonsai {
+        recipient: buyer_addr.clone(),
+        b_id: bonsai.id,
+        prices: vec![bonsai_price.clone().into()],
     };
 
-    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
-    let mut exp_res = HandleResponse::default();
-    exp_res.attributes = vec![
-        attr("action", "cut_bonsai"),
-        attr("owner", info.sender.clone()),
-        attr("bonsai_id", bonsai.id.clone()),
-    ];
+    // plenty of staking tokens don't pay for a bonsai priced in ucosm
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert!(res.is_err());
 
+    set_balance(
+        &mut deps.querier,
+        buyer_addr.clone(),
+        vec![coin(10, "ucosm")],
+    );
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
     assert!(res.is_ok());
-    assert_eq!(exp_res, res.unwrap());
 
-    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
-        .unwrap()
-        .unwrap();
+    let msg = HandleMsg::BuyBonsai { b_id: bonsai.id };
+    let buyer_info = mock_info(buyer_addr.clone(), &coins(10, "ucosm"));
+    handle(deps.as_mut(), env.clone(), buyer_info, msg).unwrap();
 
-    assert_eq!(0, gardener.bonsais.len())
+    let buyer = query_gardener(deps.as_ref(), buyer_addr).unwrap().unwrap();
+    assert_eq!(vec![bonsai], buyer.bonsais)
+}
+
+#[test]
+fn test_resale_pays_fee_and_royalty() {
+    let mut deps = mock_dependencies(&[]);
+
+    let creator_addr = HumanAddr::from("creator");
+    let collector_addr = HumanAddr::from("collector");
+    let seller_addr = HumanAddr::from("addr0001");
+    let buyer_addr = HumanAddr::from("addr0002");
+    let env = mock_env_height(100);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(1000, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 250,
+        fee_collector: collector_addr.clone(),
+        royalty_bps: 500,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    init(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(creator_addr.clone(), &[]),
+        init_msg,
+    )
+    .unwrap();
+
+    for (addr, name) in &[(&seller_addr, "leo"), (&buyer_addr, "ricky")] {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), mock_info(*addr, &[]), msg).unwrap();
+    }
+    set_balance(
+        &mut deps.querier,
+        buyer_addr.clone(),
+        coins(1000, BOND_DENOM),
+    );
+
+    // the primary sale only pays the protocol fee
+    let bonsai_id = get_random_bonsai_id(deps.as_ref());
+    let msg = HandleMsg::BuyBonsai { b_id: bonsai_id };
+    let seller_info = mock_info(seller_addr.clone(), &coins(1000, BOND_DENOM));
+    let res = handle(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    assert_eq!(
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: collector_addr.clone(),
+            amount: coins(25, BOND_DENOM),
+        })],
+        res.messages
+    );
+
+    let msg = HandleMsg::SellBonsai {
+        recipient: buyer_addr.clone(),
+        b_id: bonsai_id,
+        prices: vec![coin(1000, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+
+    let royalty_info = query_royalty_info(deps.as_ref(), bonsai_id, Uint128(1000)).unwrap();
+    assert_eq!(
+        RoyaltyInfoResponse {
+            fee_collector: collector_addr.clone(),
+            protocol_fee: Uint128(25),
+            royalty_recipient: Some(creator_addr.clone()),
+            royalty: Uint128(50),
+            seller_amount: Uint128(925),
+        },
+        royalty_info
+    );
+
+    // only the recipient of the offer can complete the sale
+    let msg = HandleMsg::BuyBonsai { b_id: bonsai_id };
+    let other_info = mock_info(HumanAddr::from("addr0003"), &coins(1000, BOND_DENOM));
+    let res = handle(deps.as_mut(), env.clone(), other_info, msg.clone());
+    match res {
+        Err(MyCustomError::NotOnSale { b_id }) => assert_eq!(bonsai_id, b_id),
+        _ => panic!("expected NotOnSale error, got {:?}", res),
+    }
+
+    let buyer_info = mock_info(buyer_addr.clone(), &coins(1000, BOND_DENOM));
+    let res = handle(deps.as_mut(), env.clone(), buyer_info, msg).unwrap();
+    assert_eq!(
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: collector_addr,
+                amount: coins(25, BOND_DENOM),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: creator_addr,
+                amount: coins(50, BOND_DENOM),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: seller_addr.clone(),
+                amount: coins(925, BOND_DENOM),
+            }),
+        ],
+        res.messages
+    );
+    assert_eq!(
+        vec![
+            attr("action", "buy_bonsai"),
+            attr("bonsai_id", bonsai_id),
+            attr("from", &seller_addr),
+            attr("to", &buyer_addr),
+            attr("price", 1000u128),
+            attr("denom", BOND_DENOM),
+            attr("height", env.block.height),
+            attr("protocol_fee", 25u128),
+            attr("royalty", 50u128),
+            attr("seller_amount", 925u128),
+        ],
+        res.attributes
+    );
+
+    let buyer = query_gardener(deps.as_ref(), buyer_addr).unwrap().unwrap();
+    assert_eq!(bonsai_id, buyer.bonsais[0].id);
+    assert!(query_invariants(deps.as_ref()).unwrap().valid)
+}
+
+#[test]
+fn test_init_with_too_high_fees_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(1000, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 5_000,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 5_001,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("creator"), &[]);
+    let res = init(deps.as_mut(), mock_env_height(100), info, init_msg);
+    match res {
+        Err(MyCustomError::InvalidBasisPoints {}) => {}
+        _ => panic!("expected InvalidBasisPoints error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_buy_bonsai_follows_pricing_curve() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let env = mock_env_height(100);
+    let info = mock_info(sender_addr.clone(), &[]);
+
+    // every sale raises the price by 10% of the base price
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(100, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::LinearCurve { step_bps: 1_000 },
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let info = mock_info(sender_addr.clone(), &coins(100, BOND_DENOM));
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyBonsai { b_id: 1 },
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![Price::from(coin(110, BOND_DENOM))],
+        query_current_price(deps.as_ref(), env.clone(), 2)
+            .unwrap()
+            .prices
+    );
+
+    // the base price is no longer enough
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        HandleMsg::BuyBonsai { b_id: 2 },
+    );
+    assert!(res.is_err());
+
+    let info = mock_info(sender_addr, &coins(110, BOND_DENOM));
+    let res = handle(deps.as_mut(), env, info, HandleMsg::BuyBonsai { b_id: 2 }).unwrap();
+    assert_eq!(attr("price", 110u128), res.attributes[4]);
+}
+
+#[test]
+fn test_dutch_auction_price_decays() {
+    let mut deps = mock_dependencies(&[]);
+
+    // the price drops by 1% of the base price every block, down to half of it
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(1000, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::DutchAuction {
+            decay_bps: 100,
+            floor_bps: 5_000,
+        },
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("creator"), &[]);
+    init(deps.as_mut(), mock_env_height(100), info, init_msg).unwrap();
+
+    let price_at = |height| {
+        query_current_price(deps.as_ref(), mock_env_height(height), 1)
+            .unwrap()
+            .prices
+    };
+    assert_eq!(vec![Price::from(coin(1000, BOND_DENOM))], price_at(100));
+    assert_eq!(vec![Price::from(coin(900, BOND_DENOM))], price_at(110));
+    assert_eq!(vec![Price::from(coin(500, BOND_DENOM))], price_at(200));
+}
+
+#[test]
+fn test_set_bonsai_price_works() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(deps.as_mut(), &env, owner.clone(), coin(10, BOND_DENOM), 10);
+
+    let msg = HandleMsg::SetBonsaiPrice {
+        b_id: 3,
+        prices: vec![coin(42, "ustake").into()],
+    };
+
+    // only the owner and the price managers can change the prices
+    let info = mock_info(HumanAddr::from("addr0001"), &[]);
+    let res = handle(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(MyCustomError::MissingRole { role }) => assert_eq!(Role::PriceManager, role),
+        _ => panic!("expected MissingRole error, got {:?}", res),
+    }
+
+    handle(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+    assert_eq!(
+        vec![Price::from(coin(42, "ustake"))],
+        query_bonsai(deps.as_ref(), 3).unwrap().prices
+    );
+    assert_eq!(
+        vec![Price::from(coin(10, BOND_DENOM))],
+        query_bonsai(deps.as_ref(), 4).unwrap().prices
+    );
+
+    let msg = HandleMsg::SetShopPrice {
+        prices: vec![coin(7, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), env, owner, msg).unwrap();
+    for bonsai in query_bonsais(deps.as_ref()).unwrap().bonsais {
+        assert_eq!(vec![Price::from(coin(7, BOND_DENOM))], bonsai.prices);
+    }
+}
+
+#[test]
+fn test_sale_event_discounts_shop_purchase() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(
+        deps.as_mut(),
+        &env,
+        owner.clone(),
+        coin(100, BOND_DENOM),
+        10,
+    );
+
+    let msg = HandleMsg::AddSaleEvent {
+        start_height: 110,
+        end_height: 120,
+        discount_percent: 25,
+    };
+    handle(deps.as_mut(), env.clone(), owner, msg).unwrap();
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(deps.as_mut(), env, mock_info(sender_addr.clone(), &[]), msg).unwrap();
+
+    // the discount is only applied while the event is running
+    let info = mock_info(sender_addr, &coins(75, BOND_DENOM));
+    let msg = HandleMsg::BuyBonsai { b_id: 1 };
+    assert!(handle(
+        deps.as_mut(),
+        mock_env_height(105),
+        info.clone(),
+        msg.clone()
+    )
+    .is_err());
+    assert!(handle(
+        deps.as_mut(),
+        mock_env_height(120),
+        info.clone(),
+        msg.clone()
+    )
+    .is_err());
+
+    let env = mock_env_height(110);
+    let current = query_current_price(deps.as_ref(), env.clone(), 1).unwrap();
+    assert_eq!(25, current.discount_percent);
+    assert_eq!(vec![Price::from(coin(75, BOND_DENOM))], current.prices);
+
+    let res = handle(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(attr("price", 75u128), res.attributes[4]);
+}
+
+#[test]
+fn test_add_invalid_sale_event_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(
+        deps.as_mut(),
+        &env,
+        owner.clone(),
+        coin(100, BOND_DENOM),
+        10,
+    );
+
+    for (start_height, end_height, discount_percent) in &[(120, 110, 10), (110, 120, 101)] {
+        let msg = HandleMsg::AddSaleEvent {
+            start_height: *start_height,
+            end_height: *end_height,
+            discount_percent: *discount_percent,
+        };
+        let res = handle(deps.as_mut(), env.clone(), owner.clone(), msg);
+        match res {
+            Err(MyCustomError::InvalidSaleEvent {}) => {}
+            _ => panic!("expected InvalidSaleEvent error, got {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn test_presale_restricts_shop_to_allowlist() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: Some(Presale {
+            public_sale_height: 200,
+            merkle_root: None,
+        }),
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    init(deps.as_mut(), env.clone(), owner.clone(), init_msg).unwrap();
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&sender_addr, &[]),
+        msg,
+    )
+    .unwrap();
+
+    let info = mock_info(&sender_addr, &coins(10, BOND_DENOM));
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyBonsai { b_id: 1 },
+    );
+    match res {
+        Err(MyCustomError::NotAllowlisted {}) => {}
+        _ => panic!("expected NotAllowlisted error, got {:?}", res),
+    }
+
+    let msg = HandleMsg::UpdateAllowlist {
+        add: vec![sender_addr.clone()],
+        remove: vec![],
+    };
+    handle(deps.as_mut(), env.clone(), owner, msg).unwrap();
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyBonsai { b_id: 1 },
+    )
+    .unwrap();
+
+    // anyone can buy once the public sale starts
+    let other_addr = HumanAddr::from("addr0002");
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("ricky"),
+    };
+    handle(deps.as_mut(), env, mock_info(&other_addr, &[]), msg).unwrap();
+    let info = mock_info(&other_addr, &coins(10, BOND_DENOM));
+    handle(
+        deps.as_mut(),
+        mock_env_height(200),
+        info,
+        HandleMsg::BuyBonsai { b_id: 2 },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_join_presale_with_merkle_proof() {
+    let mut deps = mock_dependencies(&[]);
+
+    // a single leaf tree: the root is the hash of the address
+    let sender_addr = HumanAddr::from("addr0001");
+    let root = Sha256::digest(sender_addr.as_str().as_bytes());
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: Some(Presale {
+            public_sale_height: 200,
+            merkle_root: Some(hex::encode(root)),
+        }),
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    init(deps.as_mut(), env.clone(), owner, init_msg).unwrap();
+
+    let msg = HandleMsg::JoinPresale { proof: vec![] };
+    let info = mock_info(HumanAddr::from("addr0002"), &[]);
+    let res = handle(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(MyCustomError::InvalidMerkleProof {}) => {}
+        _ => panic!("expected InvalidMerkleProof error, got {:?}", res),
+    }
+
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&sender_addr, &[]),
+        msg,
+    )
+    .unwrap();
+    let status = query_presale_status(deps.as_ref(), env, sender_addr).unwrap();
+    assert!(status.presale_active);
+    assert!(status.allowlisted);
+}
+
+#[test]
+fn test_reveal_traits_with_commitment() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: Some(5),
+        commit_reveal: true,
+    };
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(20, BOND_DENOM));
+    init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![0, 7] };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // grown and virtual bonsais wait for their owner to reveal them
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    assert!(gardener
+        .bonsais
+        .iter()
+        .all(|bonsai| bonsai.traits.is_none()));
+
+    let reveal = HandleMsg::RevealTraits {
+        b_id: 7,
+        secret: Some("secret".to_string()),
+    };
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), reveal.clone());
+    match res {
+        Err(MyCustomError::InvalidCommitment {}) => {}
+        _ => panic!("expected InvalidCommitment error, got {:?}", res),
+    }
+
+    // only the owner commits, and only once per bonsai
+    let msg = HandleMsg::CommitSeed {
+        b_id: 7,
+        commitment: hex::encode(Sha256::digest(b"secret")),
+    };
+    let stranger = mock_info(HumanAddr::from("addr0002"), &[]);
+    let res = handle(deps.as_mut(), env.clone(), stranger.clone(), msg.clone());
+    match res {
+        Err(MyCustomError::GardenerNotFound {}) => {}
+        _ => panic!("expected GardenerNotFound error, got {:?}", res),
+    }
+    handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(MyCustomError::AlreadyCommitted { b_id }) => assert_eq!(7, b_id),
+        _ => panic!("expected AlreadyCommitted error, got {:?}", res),
+    }
+
+    // the secret can only be revealed once a later block has seeded the draw,
+    // and not in the transaction recording that block
+    let res = handle(deps.as_mut(), env, info.clone(), reveal.clone());
+    match res {
+        Err(MyCustomError::InvalidCommitment {}) => {}
+        _ => panic!("expected InvalidCommitment error, got {:?}", res),
+    }
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(101),
+        info.clone(),
+        reveal.clone(),
+    );
+    match res {
+        Err(MyCustomError::InvalidCommitment {}) => {}
+        _ => panic!("expected InvalidCommitment error, got {:?}", res),
+    }
+    let wrong = HandleMsg::RevealTraits {
+        b_id: 7,
+        secret: Some("guess".to_string()),
+    };
+    let res = handle(deps.as_mut(), mock_env_height(102), info.clone(), wrong);
+    match res {
+        Err(MyCustomError::InvalidCommitment {}) => {}
+        _ => panic!("expected InvalidCommitment error, got {:?}", res),
+    }
+    let forfeit = HandleMsg::RevealTraits {
+        b_id: 7,
+        secret: None,
+    };
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(102),
+        stranger.clone(),
+        forfeit,
+    );
+    match res {
+        Err(MyCustomError::RevealNotExpired { b_id }) => assert_eq!(7, b_id),
+        _ => panic!("expected RevealNotExpired error, got {:?}", res),
+    }
+
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(102),
+        info.clone(),
+        reveal.clone(),
+    )
+    .unwrap();
+    assert_eq!(attr("action", "reveal_traits"), res.attributes[0]);
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    assert!(gardener.bonsais[1].traits.is_some());
+    assert!(gardener.bonsais[0].traits.is_none());
+
+    // the traits can't be drawn again
+    let res = handle(deps.as_mut(), mock_env_height(103), info.clone(), reveal);
+    match res {
+        Err(MyCustomError::AlreadyRevealed { b_id }) => assert_eq!(7, b_id),
+        _ => panic!("expected AlreadyRevealed error, got {:?}", res),
+    }
+
+    // past the deadline the owner can't commit anymore and anyone reveals common traits
+    let deadline = 100 + REVEAL_DEADLINE_BLOCKS;
+    let msg = HandleMsg::CommitSeed {
+        b_id: 0,
+        commitment: hex::encode(Sha256::digest(b"secret")),
+    };
+    let res = handle(deps.as_mut(), mock_env_height(deadline), info.clone(), msg);
+    match res {
+        Err(MyCustomError::RevealExpired { b_id }) => assert_eq!(0, b_id),
+        _ => panic!("expected RevealExpired error, got {:?}", res),
+    }
+    let msg = HandleMsg::RevealTraits {
+        b_id: 0,
+        secret: None,
+    };
+    handle(deps.as_mut(), mock_env_height(deadline), stranger, msg).unwrap();
+    let gardener = query_gardener(deps.as_ref(), info.sender).unwrap().unwrap();
+    assert_eq!(Rarity::Common, gardener.bonsais[0].traits.unwrap().rarity);
+}
+
+#[test]
+fn test_buy_random_bonsai_is_drawn_later() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 3,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 1_000,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: Some(1),
+        commit_reveal: false,
+    };
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(10, BOND_DENOM));
+    init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyRandomBonsai {},
+    )
+    .unwrap();
+    assert_eq!(attr("price", 10), res.attributes[3]);
+    assert!(query_random_purchase(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .is_some());
+
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyRandomBonsai {},
+    );
+    match res {
+        Err(MyCustomError::PendingRandomPurchase {}) => {}
+        _ => panic!("expected PendingRandomPurchase error, got {:?}", res),
+    }
+
+    // the bonsai is drawn in a later block
+    let res = handle(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        HandleMsg::RevealRandomBonsai {},
+    );
+    match res {
+        Err(MyCustomError::PendingRandomPurchase {}) => {}
+        _ => panic!("expected PendingRandomPurchase error, got {:?}", res),
+    }
+    // by the first transaction of anyone
+    let msg = HandleMsg::RevealRandomBonsai {};
+    let stranger = mock_info(HumanAddr::from("addr0002"), &[]);
+    let res = handle(deps.as_mut(), mock_env_height(101), stranger, msg).unwrap();
+    assert_eq!(attr("action", "reveal_random_bonsai"), res.attributes[0]);
+    assert_eq!(attr("protocol_fee", 1), res.attributes[7]);
+    assert_eq!(1, res.messages.len());
+
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(1, gardener.bonsais.len());
+    assert_eq!(1, gardener.purchased);
+    assert_eq!(res.attributes[1], attr("bonsai_id", gardener.bonsais[0].id));
+    assert!(gardener.bonsais[0].traits.is_some());
+    assert!(query_random_purchase(deps.as_ref(), info.sender)
+        .unwrap()
+        .is_none());
+    assert!(query_invariants(deps.as_ref()).unwrap().valid);
+}
+
+#[test]
+fn test_random_bonsai_refunded_when_sold_out() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(20, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 2);
+    let other = mock_info(HumanAddr::from("addr0002"), &coins(20, BOND_DENOM));
+    for (name, gardener) in [("leo", &info), ("ricky", &other)].iter() {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), (*gardener).clone(), msg).unwrap();
+    }
+
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyRandomBonsai {},
+    )
+    .unwrap();
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![0, 1] };
+    handle(deps.as_mut(), env.clone(), other, msg).unwrap();
+
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(101),
+        info.clone(),
+        HandleMsg::RevealRandomBonsai {},
+    )
+    .unwrap();
+    assert_eq!(attr("action", "refund_random_bonsai"), res.attributes[0]);
+    assert_eq!(
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: info.sender.clone(),
+            amount: coins(10, BOND_DENOM),
+        })],
+        res.messages
+    );
+
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(101),
+        info,
+        HandleMsg::BuyRandomBonsai {},
+    );
+    match res {
+        Err(MyCustomError::SoldOut {}) => {}
+        _ => panic!("expected SoldOut error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_random_purchase_counts_in_purchase_limit() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 3,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: Some(1),
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(10, BOND_DENOM));
+    init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyRandomBonsai {},
+    )
+    .unwrap();
+
+    // the random bonsai paid but not drawn yet is already counted
+    let res = handle(deps.as_mut(), env, info, HandleMsg::BuyBonsai { b_id: 0 });
+    match res {
+        Err(MyCustomError::PurchaseLimitReached { limit }) => assert_eq!(1, limit),
+        _ => panic!("expected PurchaseLimitReached error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_random_purchases_draw_every_virtual_bonsai() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 6,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: Some(1),
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(10, BOND_DENOM));
+    init(deps.as_mut(), mock_env_height(100), info.clone(), init_msg).unwrap();
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), mock_env_height(100), info.clone(), msg).unwrap();
+    let msg = HandleMsg::BuyBonsai { b_id: 3 };
+    handle(deps.as_mut(), mock_env_height(100), info.clone(), msg).unwrap();
+
+    // every bonsai left in the shop is drawn once, virtual or not
+    for height in (101..111).step_by(2) {
+        handle(
+            deps.as_mut(),
+            mock_env_height(height),
+            info.clone(),
+            HandleMsg::BuyRandomBonsai {},
+        )
+        .unwrap();
+        let msg = HandleMsg::RevealRandomBonsai {};
+        handle(
+            deps.as_mut(),
+            mock_env_height(height + 1),
+            info.clone(),
+            msg,
+        )
+        .unwrap();
+    }
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    let mut ids: Vec<u64> = gardener.bonsais.iter().map(|bonsai| bonsai.id).collect();
+    ids.sort_unstable();
+    assert_eq!(vec![0, 1, 2, 3, 4, 5], ids);
+    assert!(query_invariants(deps.as_ref()).unwrap().valid);
+
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(111),
+        info,
+        HandleMsg::BuyRandomBonsai {},
+    );
+    match res {
+        Err(MyCustomError::SoldOut {}) => {}
+        _ => panic!("expected SoldOut error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_random_bonsai_drawn_before_later_purchases() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(10, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 3);
+    let other = mock_info(HumanAddr::from("addr0002"), &coins(10, BOND_DENOM));
+    for (name, gardener) in [("leo", &info), ("ricky", &other)].iter() {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), (*gardener).clone(), msg).unwrap();
+    }
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyRandomBonsai {},
+    )
+    .unwrap();
+    // bought in the block the random bonsai is paid in, before the seed is known
+    let msg = HandleMsg::BuyBonsai { b_id: 0 };
+    handle(deps.as_mut(), env, other.clone(), msg).unwrap();
+
+    // the first transaction of the next block draws before buying
+    let msg = HandleMsg::BuyBonsai { b_id: 2 };
+    let res = handle(deps.as_mut(), mock_env_height(101), other.clone(), msg).unwrap();
+    let drawn = res
+        .attributes
+        .iter()
+        .position(|attribute| *attribute == attr("action", "reveal_random_bonsai"))
+        .unwrap();
+    assert_eq!(attr("bonsai_id", 1), res.attributes[drawn + 1]);
+    // so the drawn bonsai can't be bought anymore
+    let msg = HandleMsg::BuyBonsai { b_id: 1 };
+    assert!(handle(deps.as_mut(), mock_env_height(101), other.clone(), msg).is_err());
+
+    let gardener = query_gardener(deps.as_ref(), info.sender).unwrap().unwrap();
+    assert_eq!(1, gardener.bonsais.len());
+    assert_eq!(1, gardener.bonsais[0].id);
+    let gardener = query_gardener(deps.as_ref(), other.sender)
+        .unwrap()
+        .unwrap();
+    assert_eq!(2, gardener.bonsais.len());
+    assert!(query_invariants(deps.as_ref()).unwrap().valid);
+}
+
+#[test]
+fn test_random_bonsai_drawn_among_the_ones_its_price_covers() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(10, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 2);
+    for (b_id, amount) in [(0, 1_000), (1, 5)].iter() {
+        let msg = HandleMsg::SetBonsaiPrice {
+            b_id: *b_id,
+            prices: vec![coin(*amount, BOND_DENOM).into()],
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // the price of a new bonsai covers the cheaper one only, the difference is given back
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        HandleMsg::BuyRandomBonsai {},
+    )
+    .unwrap();
+    assert_eq!(attr("price", 10), res.attributes[3]);
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(101),
+        info.clone(),
+        HandleMsg::RevealRandomBonsai {},
+    )
+    .unwrap();
+    assert_eq!(attr("bonsai_id", 1), res.attributes[1]);
+    assert_eq!(attr("price", 5), res.attributes[4]);
+    assert_eq!(attr("refund", 5), res.attributes[8]);
+    assert_eq!(
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: info.sender.clone(),
+            amount: coins(5, BOND_DENOM),
+        })],
+        res.messages
+    );
+
+    // and none left, the whole price is given back
+    handle(
+        deps.as_mut(),
+        mock_env_height(101),
+        info.clone(),
+        HandleMsg::BuyRandomBonsai {},
+    )
+    .unwrap();
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(102),
+        info.clone(),
+        HandleMsg::RevealRandomBonsai {},
+    )
+    .unwrap();
+    assert_eq!(attr("action", "refund_random_bonsai"), res.attributes[0]);
+    assert_eq!(
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: info.sender.clone(),
+            amount: coins(10, BOND_DENOM),
+        })],
+        res.messages
+    );
+    let gardener = query_gardener(deps.as_ref(), info.sender).unwrap().unwrap();
+    assert_eq!(1, gardener.bonsais.len());
+}
+
+#[test]
+fn test_cross_bonsais_works() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(30, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 10);
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = HandleMsg::BuyBonsais {
+        b_ids: vec![0, 1, 2],
+    };
+    handle(deps.as_mut(), env, info.clone(), msg).unwrap();
+
+    let cross = |parent_a, parent_b| HandleMsg::CrossBonsais { parent_a, parent_b };
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(200),
+        info.clone(),
+        cross(0, 0),
+    );
+    match res {
+        Err(MyCustomError::DuplicatedBonsai { b_id }) => assert_eq!(0, b_id),
+        _ => panic!("expected DuplicatedBonsai error, got {:?}", res),
+    }
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(200),
+        info.clone(),
+        cross(0, 5),
+    );
+    match res {
+        Err(MyCustomError::NotBonsaiOwner { b_id }) => assert_eq!(5, b_id),
+        _ => panic!("expected NotBonsaiOwner error, got {:?}", res),
+    }
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(150),
+        info.clone(),
+        cross(0, 1),
+    );
+    match res {
+        Err(MyCustomError::NotMature { b_id }) => assert_eq!(0, b_id),
+        _ => panic!("expected NotMature error, got {:?}", res),
+    }
+
+    // the child is numbered after the whole inventory
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(200),
+        info.clone(),
+        cross(0, 1),
+    )
+    .unwrap();
+    assert_eq!(attr("bonsai_id", 10), res.attributes[1]);
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    let child = gardener.bonsais.last().unwrap();
+    assert_eq!((10, 200), (child.id, child.birth_date));
+    assert!(child.traits.is_some());
+    let lineage = query_lineage(deps.as_ref(), 10).unwrap();
+    assert_eq!((vec![0, 1], 1), (lineage.parents, lineage.generation));
+    assert_eq!(0, query_lineage(deps.as_ref(), 0).unwrap().generation);
+
+    // both parents rest before being crossed again
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(250),
+        info.clone(),
+        cross(2, 0),
+    );
+    match res {
+        Err(MyCustomError::OnCooldown { b_id, until }) => assert_eq!((0, 400), (b_id, until)),
+        _ => panic!("expected OnCooldown error, got {:?}", res),
+    }
+
+    handle(deps.as_mut(), mock_env_height(300), info, cross(10, 2)).unwrap();
+    let lineage = query_lineage(deps.as_ref(), 11).unwrap();
+    assert_eq!((vec![10, 2], 2), (lineage.parents, lineage.generation));
+    assert!(query_invariants(deps.as_ref()).unwrap().valid);
+}
+
+#[test]
+fn test_propagating_cut_keeps_the_tree() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(20, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 10);
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![0, 1] };
+    handle(deps.as_mut(), env, info.clone(), msg).unwrap();
+
+    let propagate = |b_id| HandleMsg::CutBonsai {
+        b_id,
+        propagate: true,
+    };
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(150),
+        info.clone(),
+        propagate(0),
+    );
+    match res {
+        Err(MyCustomError::NotMature { b_id }) => assert_eq!(0, b_id),
+        _ => panic!("expected NotMature error, got {:?}", res),
+    }
+
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(200),
+        info.clone(),
+        propagate(0),
+    )
+    .unwrap();
+    assert_eq!(attr("clone_id", 10), res.attributes[4]);
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(3, gardener.bonsais.len());
+    let tree = gardener.bonsais.iter().find(|b| b.id == 0).unwrap();
+    let cutting = gardener.bonsais.last().unwrap();
+    assert_eq!((10, 200), (cutting.id, cutting.birth_date));
+    let (tree_traits, cutting_traits) = (tree.traits.unwrap(), cutting.traits.unwrap());
+    assert_eq!(tree_traits.species, cutting_traits.species);
+    assert_eq!(tree_traits.rarity.previous(), cutting_traits.rarity);
+    let lineage = query_lineage(deps.as_ref(), 10).unwrap();
+    assert_eq!((vec![0], 1), (lineage.parents, lineage.generation));
+
+    // the tree rests before another cutting is taken
+    let res = handle(
+        deps.as_mut(),
+        mock_env_height(250),
+        info.clone(),
+        propagate(0),
+    );
+    match res {
+        Err(MyCustomError::OnCooldown { b_id, until }) => assert_eq!((0, 400), (b_id, until)),
+        _ => panic!("expected OnCooldown error, got {:?}", res),
+    }
+
+    // a plain cut still burns the tree
+    let msg = HandleMsg::CutBonsai {
+        b_id: 1,
+        propagate: false,
+    };
+    handle(deps.as_mut(), mock_env_height(250), info.clone(), msg).unwrap();
+    let gardener = query_gardener(deps.as_ref(), info.sender).unwrap().unwrap();
+    assert!(gardener.bonsais.iter().all(|b| b.id != 1));
+    assert!(query_invariants(deps.as_ref()).unwrap().valid);
+}
+
+#[test]
+fn test_purchase_limit_per_wallet() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: Some(2),
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    init(deps.as_mut(), env.clone(), owner, init_msg).unwrap();
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&sender_addr, &[]),
+        msg,
+    )
+    .unwrap();
+
+    let info = mock_info(&sender_addr, &coins(10, BOND_DENOM));
+    for b_id in 1..3 {
+        let msg = HandleMsg::BuyBonsai { b_id };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        HandleMsg::BuyBonsai { b_id: 3 },
+    );
+    match res {
+        Err(MyCustomError::PurchaseLimitReached { limit }) => assert_eq!(2, limit),
+        _ => panic!("expected PurchaseLimitReached error, got {:?}", res),
+    }
+
+    let status = query_presale_status(deps.as_ref(), env, sender_addr).unwrap();
+    assert!(!status.presale_active);
+    assert_eq!(2, status.purchased);
+}
+
+#[test]
+fn test_buy_bonsais_pays_summed_price() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let env = mock_env_height(100);
+    let info = mock_info(sender_addr.clone(), &[]);
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 10);
+
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = HandleMsg::BuyBonsais {
+        b_ids: vec![1, 2, 3],
+    };
+
+    // the funds cover each bonsai but not all of them together
+    let info = mock_info(sender_addr.clone(), &coins(25, BOND_DENOM));
+    assert!(handle(deps.as_mut(), env.clone(), info, msg.clone()).is_err());
+    assert_eq!(10, query_bonsais(deps.as_ref()).unwrap().bonsais.len());
+
+    let info = mock_info(sender_addr.clone(), &coins(30, BOND_DENOM));
+    let res = handle(deps.as_mut(), env, info, msg).unwrap();
+    let bought: Vec<_> = res
+        .attributes
+        .iter()
+        .filter(|attribute| attribute.key == "bonsai_id")
+        .map(|attribute| attribute.value.clone())
+        .collect();
+    assert_eq!(vec!["1", "2", "3"], bought);
+
+    let gardener = query_gardener(deps.as_ref(), sender_addr).unwrap().unwrap();
+    assert_eq!(3, gardener.bonsais.len());
+    assert!(query_invariants(deps.as_ref()).unwrap().valid)
+}
+
+#[test]
+fn test_batch_with_invalid_ids_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let env = mock_env_height(100);
+    let info = mock_info(sender_addr.clone(), &[]);
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 10);
+
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let info = mock_info(sender_addr.clone(), &coins(30, BOND_DENOM));
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![] };
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(MyCustomError::EmptyBatch {}) => {}
+        _ => panic!("expected EmptyBatch error, got {:?}", res),
+    }
+
+    let msg = HandleMsg::BuyBonsais {
+        b_ids: vec![1, 2, 1],
+    };
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(MyCustomError::DuplicatedBonsai { b_id }) => assert_eq!(1, b_id),
+        _ => panic!("expected DuplicatedBonsai error, got {:?}", res),
+    }
+
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![1, 2] };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // nothing is cut if one of the bonsais isn't owned
+    let msg = HandleMsg::CutBonsais {
+        b_ids: vec![1, 2, 3],
+    };
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(MyCustomError::NotBonsaiOwner { b_id }) => assert_eq!(3, b_id),
+        _ => panic!("expected NotBonsaiOwner error, got {:?}", res),
+    }
+    let gardener = query_gardener(deps.as_ref(), sender_addr.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(2, gardener.bonsais.len());
+
+    let msg = HandleMsg::CutBonsais { b_ids: vec![1, 2] };
+    let res = handle(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        vec![
+            attr("action", "cut_bonsai"),
+            attr("bonsai_id", 1),
+            attr("from", &sender_addr),
+            attr("height", 100),
+            attr("action", "cut_bonsai"),
+            attr("bonsai_id", 2),
+            attr("from", &sender_addr),
+            attr("height", 100),
+        ],
+        res.attributes
+    );
+    let gardener = query_gardener(deps.as_ref(), sender_addr).unwrap().unwrap();
+    assert!(gardener.bonsais.is_empty());
+}
+
+#[test]
+fn test_sell_bonsais_offers_every_bonsai() {
+    let mut deps = mock_dependencies(&[]);
+
+    let seller_addr = HumanAddr::from("addr0001");
+    let buyer_addr = HumanAddr::from("addr0002");
+    let env = mock_env_height(100);
+    setup_test(
+        deps.as_mut(),
+        &env,
+        mock_info(&seller_addr, &[]),
+        coin(10, BOND_DENOM),
+        10,
+    );
+    set_balance(
+        &mut deps.querier,
+        buyer_addr.clone(),
+        coins(1000, BOND_DENOM),
+    );
+    for (addr, name) in &[(&seller_addr, "leo"), (&buyer_addr, "ricky")] {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), mock_info(*addr, &[]), msg).unwrap();
+    }
+
+    let seller_info = mock_info(&seller_addr, &coins(20, BOND_DENOM));
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![4, 5] };
+    handle(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SellBonsais {
+        recipient: buyer_addr.clone(),
+        b_ids: vec![4, 5],
+        prices: vec![coin(10, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+
+    let msg = HandleMsg::BuyBonsais { b_ids: vec![4, 5] };
+    let buyer_info = mock_info(&buyer_addr, &coins(20, BOND_DENOM));
+    let res = handle(deps.as_mut(), env, buyer_info, msg).unwrap();
+    assert_eq!(2, res.messages.len());
+
+    let buyer = query_gardener(deps.as_ref(), buyer_addr).unwrap().unwrap();
+    assert_eq!(2, buyer.bonsais.len());
+    assert!(query_invariants(deps.as_ref()).unwrap().valid)
+}
+
+#[test]
+fn test_sale_offer_asks_its_own_price_and_can_be_cancelled() {
+    let mut deps = mock_dependencies(&[]);
+
+    let seller_addr = HumanAddr::from("addr0001");
+    let buyer_addr = HumanAddr::from("addr0002");
+    let env = mock_env_height(100);
+    setup_test(
+        deps.as_mut(),
+        &env,
+        mock_info(&seller_addr, &[]),
+        coin(10, BOND_DENOM),
+        10,
+    );
+    set_balance(
+        &mut deps.querier,
+        buyer_addr.clone(),
+        vec![coin(1000, BOND_DENOM), coin(50, "ucosm")],
+    );
+    for (addr, name) in &[(&seller_addr, "leo"), (&buyer_addr, "ricky")] {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), mock_info(*addr, &[]), msg).unwrap();
+    }
+    let seller_info = mock_info(&seller_addr, &coins(10, BOND_DENOM));
+    let msg = HandleMsg::BuyBonsai { b_id: 3 };
+    handle(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SellBonsai {
+        recipient: buyer_addr.clone(),
+        b_id: 3,
+        prices: vec![coin(50, "ucosm").into()],
+    };
+    handle(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let current = query_current_price(deps.as_ref(), env.clone(), 3).unwrap();
+    assert_eq!(vec![Price::from(coin(50, "ucosm"))], current.prices);
+
+    // the shop price of the bonsai doesn't pay for it anymore
+    let msg = HandleMsg::BuyBonsai { b_id: 3 };
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&buyer_addr, &coins(10, BOND_DENOM)),
+        msg.clone(),
+    );
+    assert!(res.is_err());
+
+    // only the seller can withdraw the offer
+    let cancel = HandleMsg::CancelSale { b_id: 3 };
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&buyer_addr, &[]),
+        cancel.clone(),
+    );
+    match res {
+        Err(MyCustomError::NoSaleOffer { b_id }) => assert_eq!(3, b_id),
+        _ => panic!("expected NoSaleOffer error, got {:?}", res),
+    }
+    let res = handle(deps.as_mut(), env.clone(), seller_info, cancel).unwrap();
+    assert_eq!(
+        vec![
+            attr("action", "cancel_sale"),
+            attr("bonsai_id", 3),
+            attr("from", &seller_addr),
+            attr("to", &buyer_addr),
+            attr("height", 100),
+        ],
+        res.attributes
+    );
+
+    let res = handle(
+        deps.as_mut(),
+        env,
+        mock_info(&buyer_addr, &coins(50, "ucosm")),
+        msg,
+    );
+    match res {
+        Err(MyCustomError::NotOnSale { b_id }) => assert_eq!(3, b_id),
+        _ => panic!("expected NotOnSale error, got {:?}", res),
+    }
+    let seller = query_gardener(deps.as_ref(), seller_addr).unwrap().unwrap();
+    assert_eq!(3, seller.bonsais[0].id);
+}
+
+#[test]
+fn test_pause_freezes_purchases() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(deps.as_mut(), &env, owner.clone(), coin(10, BOND_DENOM), 10);
+
+    let msg = HandleMsg::Pause {
+        scope: PauseScope::Purchases,
+    };
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&sender_addr, &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(MyCustomError::MissingRole { role }) => assert_eq!(Role::Pauser, role),
+        _ => panic!("expected MissingRole error, got {:?}", res),
+    }
+    handle(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    // gardeners can still join while purchases are frozen
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&sender_addr, &[]),
+        msg,
+    )
+    .unwrap();
+
+    let info = mock_info(&sender_addr, &coins(10, BOND_DENOM));
+    let msg = HandleMsg::BuyBonsai { b_id: 1 };
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(MyCustomError::ContractPaused {}) => {}
+        _ => panic!("expected ContractPaused error, got {:?}", res),
+    }
+    assert_eq!(
+        Some(PauseScope::Purchases),
+        query_pause_status(deps.as_ref()).unwrap().scope
+    );
+
+    handle(deps.as_mut(), env.clone(), owner, HandleMsg::Unpause {}).unwrap();
+    handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(None, query_pause_status(deps.as_ref()).unwrap().scope);
+}
+
+#[test]
+fn test_pause_transfers_freezes_offered_purchases() {
+    let mut deps = mock_dependencies(&[]);
+
+    let seller_addr = HumanAddr::from("addr0001");
+    let buyer_addr = HumanAddr::from("addr0002");
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(deps.as_mut(), &env, owner.clone(), coin(10, BOND_DENOM), 10);
+    set_balance(
+        &mut deps.querier,
+        buyer_addr.clone(),
+        coins(1000, BOND_DENOM),
+    );
+    for (addr, name) in &[(&seller_addr, "leo"), (&buyer_addr, "ricky")] {
+        let msg = HandleMsg::BecomeGardener {
+            name: name.to_string(),
+        };
+        handle(deps.as_mut(), env.clone(), mock_info(*addr, &[]), msg).unwrap();
+    }
+    let seller_info = mock_info(&seller_addr, &coins(10, BOND_DENOM));
+    let msg = HandleMsg::BuyBonsai { b_id: 1 };
+    handle(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let msg = HandleMsg::SellBonsai {
+        recipient: buyer_addr.clone(),
+        b_id: 1,
+        prices: vec![coin(10, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+
+    let msg = HandleMsg::Pause {
+        scope: PauseScope::Transfers,
+    };
+    handle(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    // the offered bonsai can't change hands, while the shop keeps selling
+    let buyer_info = mock_info(&buyer_addr, &coins(10, BOND_DENOM));
+    let msg = HandleMsg::BuyBonsai { b_id: 1 };
+    let res = handle(deps.as_mut(), env.clone(), buyer_info.clone(), msg.clone());
+    match res {
+        Err(MyCustomError::ContractPaused {}) => {}
+        _ => panic!("expected ContractPaused error, got {:?}", res),
+    }
+    let shop_msg = HandleMsg::BuyBonsai { b_id: 2 };
+    handle(deps.as_mut(), env.clone(), buyer_info.clone(), shop_msg).unwrap();
+
+    handle(deps.as_mut(), env.clone(), owner, HandleMsg::Unpause {}).unwrap();
+    handle(deps.as_mut(), env, buyer_info, msg).unwrap();
+    let buyer = query_gardener(deps.as_ref(), buyer_addr).unwrap().unwrap();
+    assert_eq!(2, buyer.bonsais.len());
+}
+
+#[test]
+fn test_pause_all_keeps_owner_operations() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(deps.as_mut(), &env, owner.clone(), coin(10, BOND_DENOM), 10);
+
+    let msg = HandleMsg::Pause {
+        scope: PauseScope::All,
+    };
+    handle(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    let info = mock_info(HumanAddr::from("addr0001"), &[]);
+    let res = handle(deps.as_mut(), env.clone(), info, msg);
+    match res {
+        Err(MyCustomError::ContractPaused {}) => {}
+        _ => panic!("expected ContractPaused error, got {:?}", res),
+    }
+
+    let msg = HandleMsg::SetShopPrice {
+        prices: vec![coin(20, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), env, owner, msg).unwrap();
+    assert_eq!(
+        vec![Price::from(coin(20, BOND_DENOM))],
+        query_bonsai(deps.as_ref(), 1).unwrap().prices
+    );
+}
+
+#[test]
+fn test_granted_roles_guard_operations() {
+    let mut deps = mock_dependencies(&[]);
+
+    let minter_addr = HumanAddr::from("minter");
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(deps.as_mut(), &env, owner.clone(), coin(10, BOND_DENOM), 10);
+
+    let grow = HandleMsg::GrowBonsais {
+        number: 5,
+        prices: Some(vec![coin(20, BOND_DENOM).into()]),
+    };
+    let minter = mock_info(&minter_addr, &[]);
+    let res = handle(deps.as_mut(), env.clone(), minter.clone(), grow.clone());
+    match res {
+        Err(MyCustomError::MissingRole { role }) => assert_eq!(Role::Minter, role),
+        _ => panic!("expected MissingRole error, got {:?}", res),
+    }
+
+    // only the owner grants roles
+    let msg = HandleMsg::GrantRole {
+        address: minter_addr.clone(),
+        role: Role::Minter,
+    };
+    let res = handle(deps.as_mut(), env.clone(), minter.clone(), msg.clone());
+    match res {
+        Err(MyCustomError::NotCurrentOwner { .. }) => {}
+        _ => panic!("expected NotCurrentOwner error, got {:?}", res),
+    }
+    handle(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+    assert!(
+        query_has_role(deps.as_ref(), minter_addr.clone(), Role::Minter)
+            .unwrap()
+            .has_role
+    );
+    assert!(
+        !query_has_role(deps.as_ref(), minter_addr.clone(), Role::Pauser)
+            .unwrap()
+            .has_role
+    );
+
+    // new bonsais are numbered after the ones grown at init
+    handle(
+        deps.as_mut(),
+        mock_env_height(150),
+        minter.clone(),
+        grow.clone(),
+    )
+    .unwrap();
+    let bonsais = query_bonsais(deps.as_ref()).unwrap().bonsais;
+    assert_eq!(15, bonsais.len());
+    let mut bonsai = bonsais[14].clone();
+    assert!(bonsai.traits.take().is_some());
+    assert_eq!(
+        Bonsai::new(14, 150, vec![coin(20, BOND_DENOM).into()]),
+        bonsai
+    );
+    assert!(query_invariants(deps.as_ref()).unwrap().valid);
+
+    let msg = HandleMsg::RevokeRole {
+        address: minter_addr.clone(),
+        role: Role::Minter,
+    };
+    handle(deps.as_mut(), env.clone(), owner, msg).unwrap();
+    assert!(
+        !query_has_role(deps.as_ref(), minter_addr, Role::Minter)
+            .unwrap()
+            .has_role
+    );
+    assert!(handle(deps.as_mut(), env, minter, grow).is_err());
+}
+
+#[test]
+fn test_get_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: Some(HumanAddr::from("owner")),
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![HumanAddr::from("token0001")],
+        protocol_fee_bps: 250,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 500,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: Some(3),
+        max_initial_inventory: Some(50),
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("creator"), &[]);
+    init(deps.as_mut(), mock_env_height(100), info, init_msg).unwrap();
+
+    assert_eq!(
+        ConfigResponse {
+            owner: HumanAddr::from("owner"),
+            default_prices: vec![coin(10, BOND_DENOM).into()],
+            cw20_whitelist: vec![HumanAddr::from("token0001")],
+            fee_collector: HumanAddr::from("collector"),
+            protocol_fee_bps: 250,
+            royalty_bps: 500,
+            pricing: PricingMode::Fixed {},
+            presale: None,
+            max_per_wallet: Some(3),
+            max_initial_inventory: 50,
+            commit_reveal: false,
+        },
+        query_config(deps.as_ref()).unwrap()
+    );
+}
+
+#[test]
+fn test_update_config_works() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    setup_test(deps.as_mut(), &env, owner.clone(), coin(10, BOND_DENOM), 10);
+
+    let msg = HandleMsg::UpdateConfig {
+        owner: Some(HumanAddr::from("new_owner")),
+        default_prices: None,
+        cw20_whitelist: None,
+        fee_collector: None,
+        protocol_fee_bps: Some(100),
+        pricing: None,
+        presale: None,
+        clear_presale: false,
+        max_per_wallet: Some(5),
+        clear_max_per_wallet: false,
+        max_initial_inventory: None,
+    };
+    let info = mock_info(HumanAddr::from("addr0001"), &[]);
+    let res = handle(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(MyCustomError::NotCurrentOwner { .. }) => {}
+        _ => panic!("expected NotCurrentOwner error, got {:?}", res),
+    }
+
+    // the fees can't go over the sale price
+    let invalid = HandleMsg::UpdateConfig {
+        owner: None,
+        default_prices: None,
+        cw20_whitelist: None,
+        fee_collector: None,
+        protocol_fee_bps: Some(10_001),
+        pricing: None,
+        presale: None,
+        clear_presale: false,
+        max_per_wallet: None,
+        clear_max_per_wallet: false,
+        max_initial_inventory: None,
+    };
+    let res = handle(deps.as_mut(), env.clone(), owner.clone(), invalid);
+    match res {
+        Err(MyCustomError::InvalidBasisPoints {}) => {}
+        _ => panic!("expected InvalidBasisPoints error, got {:?}", res),
+    }
+
+    handle(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap();
+    let config = query_config(deps.as_ref()).unwrap();
+    assert_eq!(HumanAddr::from("new_owner"), config.owner);
+    assert_eq!(100, config.protocol_fee_bps);
+    assert_eq!(Some(5), config.max_per_wallet);
+    assert_eq!(
+        vec![Price::from(coin(10, BOND_DENOM))],
+        config.default_prices
+    );
+
+    // the previous owner lost its rights
+    assert!(handle(deps.as_mut(), env.clone(), owner, msg).is_err());
+
+    // the purchase limit can be lifted, but not set and lifted at once
+    let new_owner = mock_info(HumanAddr::from("new_owner"), &[]);
+    let mut clear = HandleMsg::UpdateConfig {
+        owner: None,
+        default_prices: None,
+        cw20_whitelist: None,
+        fee_collector: None,
+        protocol_fee_bps: None,
+        pricing: None,
+        presale: None,
+        clear_presale: false,
+        max_per_wallet: Some(3),
+        clear_max_per_wallet: true,
+        max_initial_inventory: None,
+    };
+    let res = handle(deps.as_mut(), env.clone(), new_owner.clone(), clear.clone());
+    assert!(res.is_err());
+    if let HandleMsg::UpdateConfig { max_per_wallet, .. } = &mut clear {
+        *max_per_wallet = None;
+    }
+    handle(deps.as_mut(), env, new_owner, clear).unwrap();
+    let config = query_config(deps.as_ref()).unwrap();
+    assert_eq!(None, config.max_per_wallet);
+    assert_eq!(100, config.protocol_fee_bps);
+}
+
+#[test]
+fn test_update_config_with_overflowing_fee_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 500,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let env = mock_env_height(100);
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    init(deps.as_mut(), env.clone(), owner.clone(), init_msg).unwrap();
+
+    // the fee added to the royalty doesn't fit in a u64
+    let msg = HandleMsg::UpdateConfig {
+        owner: None,
+        default_prices: None,
+        cw20_whitelist: None,
+        fee_collector: None,
+        protocol_fee_bps: Some(u64::MAX),
+        pricing: None,
+        presale: None,
+        clear_presale: false,
+        max_per_wallet: None,
+        clear_max_per_wallet: false,
+        max_initial_inventory: None,
+    };
+    let res = handle(deps.as_mut(), env, owner, msg);
+    match res {
+        Err(MyCustomError::InvalidBasisPoints {}) => {}
+        _ => panic!("expected InvalidBasisPoints error, got {:?}", res),
+    }
+    assert_eq!(0, query_config(deps.as_ref()).unwrap().protocol_fee_bps);
+}
+
+#[test]
+fn test_init_with_invalid_auction_floor_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(1000, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::DutchAuction {
+            decay_bps: 100,
+            floor_bps: 10_001,
+        },
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("creator"), &[]);
+    let res = init(deps.as_mut(), mock_env_height(100), info, init_msg);
+    match res {
+        Err(MyCustomError::InvalidBasisPoints {}) => {}
+        _ => panic!("expected InvalidBasisPoints error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_init_with_too_steep_curve_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(1000, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::ExponentialCurve {
+            step_bps: MAX_STEP_BPS + 1,
+        },
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("creator"), &[]);
+    let res = init(deps.as_mut(), mock_env_height(100), info, init_msg);
+    match res {
+        Err(MyCustomError::InvalidBasisPoints {}) => {}
+        _ => panic!("expected InvalidBasisPoints error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_init_with_invalid_inventory_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 0,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
+    };
+    let info = mock_info(HumanAddr::from("creator"), &[]);
+    let env = mock_env_height(100);
+
+    let res = init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone());
+    match res {
+        Err(MyCustomError::EmptyInventory {}) => {}
+        _ => panic!("expected EmptyInventory error, got {:?}", res),
+    }
+
+    for prices in &[
+        vec![],
+        vec![coin(0, BOND_DENOM).into()],
+        vec![coin(10, "").into()],
+    ] {
+        let msg = InitMsg {
+            number: 10,
+            prices: prices.clone(),
+            ..init_msg.clone()
+        };
+        let res = init(deps.as_mut(), env.clone(), info.clone(), msg);
+        match res {
+            Err(MyCustomError::InvalidPrice {}) => {}
+            _ => panic!("expected InvalidPrice error, got {:?}", res),
+        }
+    }
+
+    let msg = InitMsg {
+        number: 10,
+        max_initial_inventory: Some(MAX_INITIAL_INVENTORY + 1),
+        commit_reveal: false,
+        ..init_msg
+    };
+    let res = init(deps.as_mut(), env, info, msg);
+    match res {
+        Err(MyCustomError::InventoryTooLarge { max }) => assert_eq!(MAX_INITIAL_INVENTORY, max),
+        _ => panic!("expected InventoryTooLarge error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_virtual_bonsais_are_grown_when_bought() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(10, BOND_DENOM).into()],
+        number: 1_000_000_000,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: Some(2),
+        commit_reveal: false,
+    };
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(20, BOND_DENOM));
+    init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+    assert_eq!(2, query_bonsais(deps.as_ref()).unwrap().bonsais.len());
+
+    // a virtual bonsai is shown as if grown at init
+    let bonsai = query_bonsai(deps.as_ref(), 999_999_999).unwrap();
+    assert_eq!(100, bonsai.birth_date);
+    assert_eq!(vec![Price::from(coin(10, BOND_DENOM))], bonsai.prices);
+    assert!(query_bonsai(deps.as_ref(), 1_000_000_000).is_err());
+
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = HandleMsg::BuyBonsais {
+        b_ids: vec![1, 999_999_999],
+    };
+    handle(
+        deps.as_mut(),
+        mock_env_height(110),
+        info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(999_999_999, gardener.bonsais[1].id);
+    assert_eq!(100, gardener.bonsais[1].birth_date);
+    assert_eq!(1, query_bonsais(deps.as_ref()).unwrap().bonsais.len());
+
+    // the virtual bonsai now has a record and is no longer sold by the shop
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(MyCustomError::NotOnSale { b_id }) => assert_eq!(1, b_id),
+        _ => panic!("expected NotOnSale error, got {:?}", res),
+    }
+
+    // grown bonsais are numbered after the whole inventory
+    let msg = HandleMsg::GrowBonsais {
+        number: 2,
+        prices: None,
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let bonsais = query_bonsais(deps.as_ref()).unwrap().bonsais;
+    assert_eq!(
+        vec![0, 1_000_000_000, 1_000_000_001],
+        bonsais.iter().map(|bonsai| bonsai.id).collect::<Vec<_>>()
+    );
+    let inventory = inventory_store_read(&deps.storage).load().unwrap();
+    assert_eq!(1_000_000_002, inventory.total_supply);
+    assert_eq!(5, inventory.minted);
+
+    // a single transaction can't grow more than the configured maximum
+    let msg = HandleMsg::GrowBonsais {
+        number: 3,
+        prices: None,
+    };
+    let res = handle(deps.as_mut(), env, info, msg);
+    match res {
+        Err(MyCustomError::InventoryTooLarge { max }) => assert_eq!(2, max),
+        _ => panic!("expected InventoryTooLarge error, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_virtual_bonsais_are_priced_like_grown_ones() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = InitMsg {
+        owner: None,
+        prices: vec![coin(1000, BOND_DENOM).into()],
+        number: 10,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::DutchAuction {
+            decay_bps: 10,
+            floor_bps: 5_000,
+        },
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: Some(5),
+        commit_reveal: false,
+    };
+    let owner = mock_info(HumanAddr::from("creator"), &[]);
+    init(deps.as_mut(), mock_env_height(100), owner.clone(), init_msg).unwrap();
+
+    let price_at = |deps: Deps, height, b_id| {
+        query_current_price(deps, mock_env_height(height), b_id)
+            .unwrap()
+            .prices
+    };
+    // the virtual bonsais age from init like the grown ones
+    assert_eq!(
+        vec![Price::from(coin(700, BOND_DENOM))],
+        price_at(deps.as_ref(), 400, 0)
+    );
+    assert_eq!(
+        vec![Price::from(coin(700, BOND_DENOM))],
+        price_at(deps.as_ref(), 400, 5)
+    );
+
+    // and follow the shop price
+    let msg = HandleMsg::SetShopPrice {
+        prices: vec![coin(5000, BOND_DENOM).into()],
+    };
+    handle(deps.as_mut(), mock_env_height(100), owner, msg).unwrap();
+    assert_eq!(
+        vec![Price::from(coin(5000, BOND_DENOM))],
+        price_at(deps.as_ref(), 100, 0)
+    );
+    assert_eq!(
+        vec![Price::from(coin(5000, BOND_DENOM))],
+        price_at(deps.as_ref(), 100, 5)
+    );
+}
+
+#[test]
+fn test_sell_bonsai_to_self_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let bonsai_price = coin(10, BOND_DENOM);
+    let env = mock_env_height(100);
+    let info = mock_info(sender_addr.clone(), &coins(25, BOND_DENOM));
+
+    // setup test environment
+    setup_test(deps.as_mut(), &env, info.clone(), bonsai_price.clone(), 10);
+    set_validator(&mut deps.querier, BOND_DENOM);
+    set_balance(
+        &mut deps.querier,
+        sender_addr.clone(),
+        vec![coin(1000, BOND_DENOM)],
+    );
+
+    let msg = HandleMsg::BecomeGardener {
+        name: String::from("leo"),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let bonsai_id = get_random_bonsai_id(deps.as_ref());
+    let msg = HandleMsg::BuyBonsai { b_id: bonsai_id };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SellBonsai {
+        recipient: sender_addr.clone(),
+        b_id: bonsai_id,
+        prices: vec![bonsai_price.clone().into()],
+    };
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(MyCustomError::AlreadyOwned { b_id }) => assert_eq!(bonsai_id, b_id),
+        _ => panic!("expected AlreadyOwned error, got {:?}", res),
+    }
+
+    // the bonsai is still owned once by the seller
+    let gardener = query_gardener(deps.as_ref(), sender_addr.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(1, gardener.bonsais.len());
+    assert!(query_invariants(deps.as_ref()).unwrap().valid)
+}
+
+#[test]
+fn test_cut_bonsai_works() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let bonsai_price = coin(10, BOND_DENOM);
+    let bonsai_height = 100;
+    let env = mock_env_height(bonsai_height);
+    let info = mock_info(sender_addr.clone(), &coins(25, BOND_DENOM));
+
+    // setup test environment
+    setup_test(deps.as_mut(), &env, info.clone(), bonsai_price.clone(), 10);
+
+    let canonical_addr = &deps.api.canonical_address(&info.sender.clone()).unwrap();
+    let bonsai = Bonsai::new(10, bonsai_height, vec![bonsai_price.into()]);
+    let gardener = Gardener::new(
+        "leo".to_string(),
+        canonical_addr.clone(),
+        vec![bonsai.clone()],
+    );
+
+    let _ = gardeners_store(&mut deps.storage).save(canonical_addr.as_slice(), &gardener);
+
+    let msg = HandleMsg::CutBonsai {
+        b_id: bonsai.id.clone(),
+        propagate: false,
+    };
+
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    let mut exp_res = HandleResponse::default();
+    exp_res.attributes = vec![
+        attr("action", "cut_bonsai"),
+        attr("bonsai_id", bonsai.id.clone()),
+        attr("from", info.sender.clone()),
+        attr("height", bonsai_height),
+    ];
+
+    assert!(res.is_ok());
+    assert_eq!(exp_res, res.unwrap());
+
+    let gardener = query_gardener(deps.as_ref(), info.sender.clone())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(0, gardener.bonsais.len())
+}
+
+#[test]
+fn test_cut_bonsai_leaves_tombstone() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env_height(100);
+    let info = mock_info(HumanAddr::from("addr0001"), &coins(30, BOND_DENOM));
+    setup_test(deps.as_mut(), &env, info.clone(), coin(10, BOND_DENOM), 10);
+    let msg = HandleMsg::BecomeGardener {
+        name: "leo".to_string(),
+    };
+    handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = HandleMsg::BuyBonsais {
+        b_ids: vec![0, 1, 2],
+    };
+    handle(deps.as_mut(), env, info.clone(), msg).unwrap();
+
+    let msg = HandleMsg::CutBonsais { b_ids: vec![0, 2] };
+    handle(deps.as_mut(), mock_env_height(120), info.clone(), msg).unwrap();
+
+    let supply = query_supply(deps.as_ref()).unwrap();
+    assert_eq!(
+        SupplyResponse {
+            total_supply: 10,
+            minted: 10,
+            sold: 3,
+            owned: 1,
+            burned: 2,
+        },
+        supply
+    );
+    let tombstone = query_tombstone(deps.as_ref(), 2).unwrap().unwrap();
+    let canonical_addr = deps.api.canonical_address(&info.sender).unwrap();
+    assert_eq!(
+        (2, canonical_addr, 120),
+        (tombstone.id, tombstone.last_owner, tombstone.cut_height)
+    );
+    assert_eq!(None, query_tombstone(deps.as_ref(), 1).unwrap());
+}
+
+#[test]
+fn test_cut_bonsai_not_owned_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let sender_addr = HumanAddr::from("addr0001");
+    let bonsai_price = coin(10, BOND_DENOM);
+    let bonsai_height = 100;
+    let env = mock_env_height(bonsai_height);
+    let info = mock_info(sender_addr.clone(), &coins(25, BOND_DENOM));
+
+    // setup test environment
+    setup_test(deps.as_mut(), &env, info.clone(), bonsai_price.clone(), 10);
+
+    let msg = HandleMsg::CutBonsai {
+        b_id: 1,
+        propagate: false,
+    };
+
+    // the sender is not a gardener yet
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(MyCustomError::GardenerNotFound {}) => {}
+        _ => panic!("expected GardenerNotFound error, got {:?}", res),
+    }
+
+    let canonical_addr = &deps.api.canonical_address(&info.sender.clone()).unwrap();
+    let gardener = Gardener::new("leo".to_string(), canonical_addr.clone(), vec![]);
+    let _ = gardeners_store(&mut deps.storage).save(canonical_addr.as_slice(), &gardener);
+
+    // the sender is a gardener but doesn't own the bonsai
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(MyCustomError::NotBonsaiOwner { b_id }) => assert_eq!(1, b_id),
+        _ => panic!("expected NotBonsaiOwner error, got {:?}", res),
+    }
+
+    // the shop still has all of its bonsais
+    let bonsais = query_bonsais(deps.as_ref()).unwrap();
+    assert_eq!(10, bonsais.bonsais.len())
 }
 
 #[test]
@@ -283,7 +3197,7 @@ fn query_gardener_works() {
     let env = mock_env_height(bonsai_height);
     setup_test(deps.as_mut(), &env, info.clone(), bonsai_price.clone(), 10);
 
-    let bonsai = Bonsai::new(10, bonsai_height, bonsai_price);
+    let bonsai = Bonsai::new(10, bonsai_height, vec![bonsai_price.into()]);
     let canonical_addr = &deps.api.canonical_address(&sender_addr).unwrap();
 
     let gardener = Gardener::new("leo".to_string(), canonical_addr.clone(), vec![bonsai]);
@@ -308,7 +3222,7 @@ fn query_all_gardeners_works() {
     let info = mock_info(sender_addr.clone(), &coins(25, BOND_DENOM));
     setup_test(deps.as_mut(), &env, info.clone(), bonsai_price.clone(), 10);
 
-    let bonsai = Bonsai::new(10, bonsai_height, bonsai_price);
+    let bonsai = Bonsai::new(10, bonsai_height, vec![bonsai_price.into()]);
     let canonical_addr = &deps.api.canonical_address(&sender_addr).unwrap();
     let other_addr = HumanAddr::from("addr0002");
     let other_addr = &deps.api.canonical_address(&other_addr).unwrap();
@@ -1,4 +1,6 @@
-use cosmwasm_std::{CanonicalAddr, StdError};
+use cosmwasm_std::{CanonicalAddr, HumanAddr, StdError};
+
+use crate::state::Role;
 use thiserror::Error;
 
 // thiserror implements Display and ToString if you
@@ -16,4 +18,66 @@ pub enum MyCustomError {
     },
     #[error("Messages empty. Must reflect at least one message")]
     MessagesEmpty,
+    #[error("No gardener found with the given address")]
+    GardenerNotFound {},
+    #[error("Permission denied: the sender does not own the bonsai with id {b_id}")]
+    NotBonsaiOwner { b_id: u64 },
+    #[error("No bonsai with id {b_id} found in the shop")]
+    BonsaiNotFound { b_id: u64 },
+    #[error("The bonsai with id {b_id} is already owned by the recipient")]
+    AlreadyOwned { b_id: u64 },
+    #[error("The cw20 token {token} is not accepted as payment")]
+    TokenNotWhitelisted { token: HumanAddr },
+    #[error("Fees and royalties can't exceed 10000 basis points of the sale price")]
+    InvalidBasisPoints {},
+    #[error("The bonsai with id {b_id} is not on sale for the sender")]
+    NotOnSale { b_id: u64 },
+    #[error("The sender has no sale offer for the bonsai with id {b_id}")]
+    NoSaleOffer { b_id: u64 },
+    #[error("A sale event must end after its start with a discount of at most 100 percent")]
+    InvalidSaleEvent {},
+    #[error("The presale is only open to allowlisted gardeners")]
+    NotAllowlisted {},
+    #[error("The proof doesn't match the presale merkle root")]
+    InvalidMerkleProof {},
+    #[error("A gardener can't buy more than {limit} bonsais from the shop")]
+    PurchaseLimitReached { limit: u64 },
+    #[error("No bonsai ids given")]
+    EmptyBatch {},
+    #[error("The bonsai with id {b_id} is given more than once")]
+    DuplicatedBonsai { b_id: u64 },
+    #[error("The contract is paused")]
+    ContractPaused {},
+    #[error("Permission denied: the sender doesn't have the {role:?} role")]
+    MissingRole { role: Role },
+    #[error("The shop must grow at least one bonsai")]
+    EmptyInventory {},
+    #[error("Can't grow more than {max} bonsais in a single transaction")]
+    InventoryTooLarge { max: u64 },
+    #[error("Prices can't be empty and need a denom and a non zero amount")]
+    InvalidPrice {},
+    #[error("The shop price is too high to be computed")]
+    PriceOverflow {},
+    #[error("The secret doesn't match a commitment made in an earlier block")]
+    InvalidCommitment {},
+    #[error("The traits of the bonsai with id {b_id} are already revealed")]
+    AlreadyRevealed { b_id: u64 },
+    #[error("A secret is already committed for the bonsai with id {b_id}")]
+    AlreadyCommitted { b_id: u64 },
+    #[error("The traits of the bonsai with id {b_id} can no longer be revealed with a secret")]
+    RevealExpired { b_id: u64 },
+    #[error("The owner of the bonsai with id {b_id} can still reveal its traits")]
+    RevealNotExpired { b_id: u64 },
+    #[error("The random bonsai bought in this block is only drawn in a later one")]
+    PendingRandomPurchase {},
+    #[error("Too many random bonsais were bought in this block")]
+    RandomDrawsFull {},
+    #[error("No bonsai left in the shop")]
+    SoldOut {},
+    #[error("The bonsai with id {b_id} is too young to be crossed")]
+    NotMature { b_id: u64 },
+    #[error("The bonsai with id {b_id} can't be crossed before height {until}")]
+    OnCooldown { b_id: u64, until: u64 },
+    #[error("The traits of the bonsai with id {b_id} must be revealed first")]
+    TraitsNotRevealed { b_id: u64 },
 }
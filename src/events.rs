@@ -0,0 +1,104 @@
+use cosmwasm_std::{attr, Attribute, Env, HumanAddr};
+
+use crate::state::Price;
+
+// the attributes every event starts with, in this order. `bonsai_id`, `from`, `to`,
+// `price` and `denom` are left out when they don't apply to the action, the attributes
+// specific to the action follow `height`
+pub const ACTION: &str = "action";
+pub const BONSAI_ID: &str = "bonsai_id";
+pub const FROM: &str = "from";
+pub const TO: &str = "to";
+// amount of the price, followed by its native denom or cw20 contract address
+pub const PRICE: &str = "price";
+pub const DENOM: &str = "denom";
+pub const HEIGHT: &str = "height";
+
+// the actions
+pub const INIT: &str = "grown_bonsais";
+pub const GRANT_ROLE: &str = "grant_role";
+pub const REVOKE_ROLE: &str = "revoke_role";
+pub const GROW_BONSAIS: &str = "grow_bonsais";
+pub const PAUSE: &str = "pause";
+pub const UNPAUSE: &str = "unpause";
+pub const UPDATE_CONFIG: &str = "update_config";
+pub const SET_BONSAI_PRICE: &str = "set_bonsai_price";
+pub const SET_SHOP_PRICE: &str = "set_shop_price";
+pub const ADD_SALE_EVENT: &str = "add_sale_event";
+pub const UPDATE_ALLOWLIST: &str = "update_allowlist";
+pub const JOIN_PRESALE: &str = "join_presale";
+pub const COMMIT_SEED: &str = "commit_seed";
+pub const REVEAL_TRAITS: &str = "reveal_traits";
+pub const BECOME_GARDENER: &str = "become_gardener";
+pub const BUY_BONSAI: &str = "buy_bonsai";
+pub const BUY_RANDOM_BONSAI: &str = "buy_random_bonsai";
+pub const REFUND_RANDOM_BONSAI: &str = "refund_random_bonsai";
+pub const REVEAL_RANDOM_BONSAI: &str = "reveal_random_bonsai";
+pub const SELL_BONSAI: &str = "sell_bonsai";
+pub const CANCEL_SALE: &str = "cancel_sale";
+pub const CROSS_BONSAIS: &str = "cross_bonsais";
+pub const PROPAGATE_BONSAI: &str = "propagate_bonsai";
+pub const CUT_BONSAI: &str = "cut_bonsai";
+
+/// the standard attributes of an action. The shop is the contract address
+pub struct Event {
+    action: &'static str,
+    bonsai_id: Option<u64>,
+    from: Option<HumanAddr>,
+    to: Option<HumanAddr>,
+    prices: Vec<Price>,
+    height: u64,
+}
+
+impl Event {
+    pub fn new(action: &'static str, env: &Env) -> Event {
+        Event {
+            action,
+            bonsai_id: None,
+            from: None,
+            to: None,
+            prices: vec![],
+            height: env.block.height,
+        }
+    }
+
+    pub fn bonsai_id(mut self, id: u64) -> Event {
+        self.bonsai_id = Some(id);
+        self
+    }
+
+    pub fn from(mut self, address: &HumanAddr) -> Event {
+        self.from = Some(address.clone());
+        self
+    }
+
+    pub fn to(mut self, address: &HumanAddr) -> Event {
+        self.to = Some(address.clone());
+        self
+    }
+
+    /// a price and its denom, repeated for each price accepted
+    pub fn price(mut self, price: &Price) -> Event {
+        self.prices.push(price.clone());
+        self
+    }
+
+    pub fn attributes(self) -> Vec<Attribute> {
+        let mut attributes = vec![attr(ACTION, self.action)];
+        if let Some(id) = self.bonsai_id {
+            attributes.push(attr(BONSAI_ID, id));
+        }
+        if let Some(from) = self.from {
+            attributes.push(attr(FROM, from));
+        }
+        if let Some(to) = self.to {
+            attributes.push(attr(TO, to));
+        }
+        for price in self.prices.iter() {
+            attributes.push(attr(PRICE, price.amount()));
+            attributes.push(attr(DENOM, price.asset()));
+        }
+        attributes.push(attr(HEIGHT, self.height));
+        attributes
+    }
+}
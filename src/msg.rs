@@ -1,32 +1,395 @@
-use crate::state::Gardener;
-use cosmwasm_std::{Coin, HumanAddr};
+use crate::state::{
+    Bonsai, Gardener, GardenerScores, HistoryEntry, Leaderboard, PauseScope, Presale, Price,
+    PricingMode, Rarity, Role, SaleEvent, Species,
+};
+use cosmwasm_std::{HumanAddr, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InitMsg {
-    pub price: Coin,
+    // the sender if not given
+    pub owner: Option<HumanAddr>,
+    // every bonsai accepts any one of these prices
+    pub prices: Vec<Price>,
     pub number: u64,
+    // cw20 contracts whose tokens can be used to buy bonsais
+    pub cw20_whitelist: Vec<HumanAddr>,
+    // share of every sale, in basis points, paid to the fee collector
+    pub protocol_fee_bps: u64,
+    pub fee_collector: HumanAddr,
+    // share of every resale, in basis points, paid back to the contract creator
+    pub royalty_bps: u64,
+    // how the shop prices evolve with sales or with time
+    pub pricing: PricingMode,
+    // restrict the shop to an allowlist until the public sale
+    pub presale: Option<Presale>,
+    pub max_per_wallet: Option<u64>,
+    // bonsais grown at init, the rest of `number` is grown when bought
+    pub max_initial_inventory: Option<u64>,
+    // let the owners reveal the traits of their bonsais with a committed secret
+    pub commit_reveal: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
-    BecomeGardener { name: String },
+    BecomeGardener {
+        name: String,
+    },
+    BuyBonsai {
+        b_id: u64,
+    },
+    /// offer a bonsai to the recipient at the asking prices, the recipient completes
+    /// the sale paying one of them with `BuyBonsai`
+    SellBonsai {
+        recipient: HumanAddr,
+        b_id: u64,
+        prices: Vec<Price>,
+    },
+    /// burn the bonsai, or when propagating take a cutting that grows into a young clone
+    CutBonsai {
+        b_id: u64,
+        #[serde(default)]
+        propagate: bool,
+    },
+    /// buy all the bonsais at once, paying the sum of their prices
+    BuyBonsais {
+        b_ids: Vec<u64>,
+    },
+    /// offer all the bonsais to the recipient at once, each one at the asking prices
+    SellBonsais {
+        recipient: HumanAddr,
+        b_ids: Vec<u64>,
+        prices: Vec<Price>,
+    },
+    /// withdraw the offer made for a bonsai of the sender
+    CancelSale {
+        b_id: u64,
+    },
+    CutBonsais {
+        b_ids: Vec<u64>,
+    },
+    /// hook called by a whitelisted cw20 contract when tokens are sent to the shop
+    Receive(Cw20ReceiveMsg),
+    /// owner or price manager only: change the accepted prices of a bonsai still in the shop
+    SetBonsaiPrice {
+        b_id: u64,
+        prices: Vec<Price>,
+    },
+    /// owner or price manager only: change the accepted prices of every bonsai in the shop
+    SetShopPrice {
+        prices: Vec<Price>,
+    },
+    /// owner or price manager only: discount every shop bonsai from `start_height` until right before `end_height`
+    AddSaleEvent {
+        start_height: u64,
+        end_height: u64,
+        discount_percent: u64,
+    },
+    /// owner only: add or remove gardeners from the presale allowlist
+    UpdateAllowlist {
+        add: Vec<HumanAddr>,
+        remove: Vec<HumanAddr>,
+    },
+    /// join the presale allowlist with a proof against its merkle root
+    JoinPresale {
+        proof: Vec<String>,
+    },
+    /// breed a new bonsai from two mature bonsais of the sender, which then rest for a while
+    CrossBonsais {
+        parent_a: u64,
+        parent_b: u64,
+    },
+    /// pay the shop price of a new bonsai for one drawn at random by the first transaction
+    /// of a later block, among the shop bonsais the price covers. What the drawn bonsai
+    /// costs less is given back, and the whole price if the price covers none
+    BuyRandomBonsai {},
+    /// draw the random bonsais paid in earlier blocks, as any later transaction does.
+    /// Fails while the random bonsai of the sender can't be drawn yet
+    RevealRandomBonsai {},
+    /// commit to a secret for an owned bonsai bought with commit reveal, given as
+    /// the hex encoded sha256 of it. Only once per bonsai and before the reveal deadline
+    CommitSeed {
+        b_id: u64,
+        commitment: String,
+    },
+    /// draw the traits of a bonsai from the committed secret, in a later block.
+    /// Past the deadline anyone can draw them without a secret, and they're common
+    RevealTraits {
+        b_id: u64,
+        secret: Option<String>,
+    },
+    /// owner or pauser only: freeze the operations in the scope, queries keep working
+    Pause {
+        scope: PauseScope,
+    },
+    /// owner or pauser only
+    Unpause {},
+    /// minter only: grow new bonsais in the shop
+    GrowBonsais {
+        number: u64,
+        // the default prices if not given
+        prices: Option<Vec<Price>>,
+    },
+    /// owner only: change the given fields of the config. The royalty and the commit
+    /// reveal are fixed at init, as they are given to the bonsais when they are grown
+    UpdateConfig {
+        owner: Option<HumanAddr>,
+        default_prices: Option<Vec<Price>>,
+        cw20_whitelist: Option<Vec<HumanAddr>>,
+        fee_collector: Option<HumanAddr>,
+        protocol_fee_bps: Option<u64>,
+        pricing: Option<PricingMode>,
+        presale: Option<Presale>,
+        // end the presale, can't be given along with a new presale
+        #[serde(default)]
+        clear_presale: bool,
+        max_per_wallet: Option<u64>,
+        // lift the purchase limit, can't be given along with a new limit
+        #[serde(default)]
+        clear_max_per_wallet: bool,
+        max_initial_inventory: Option<u64>,
+    },
+    /// owner only: let the address perform the operations of the role
+    GrantRole {
+        address: HumanAddr,
+        role: Role,
+    },
+    /// owner only
+    RevokeRole {
+        address: HumanAddr,
+        role: Role,
+    },
+}
+
+/// messages that can be embedded in a cw20 `Send` to the shop
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
     BuyBonsai { b_id: u64 },
-    SellBonsai { recipient: HumanAddr, b_id: u64 },
-    CutBonsai { b_id: u64 },
+    BuyBonsais { b_ids: Vec<u64> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    GetConfig {},
     GetBonsais {},
-    GetGardener { sender: HumanAddr },
+    GetBonsai {
+        b_id: u64,
+    },
+    GetGardener {
+        sender: HumanAddr,
+    },
     GetGardeners {},
+    GetRoyaltyInfo {
+        b_id: u64,
+        sale_price: Uint128,
+    },
+    /// the prices the bonsai can be bought at in the current block
+    GetCurrentPrice {
+        b_id: u64,
+    },
+    GetSaleEvents {},
+    GetPresaleStatus {
+        address: HumanAddr,
+    },
+    GetPauseStatus {},
+    /// the parents and the generation of the bonsai
+    GetLineage {
+        b_id: u64,
+    },
+    /// the grown bonsais matching the filter, in the given order (oldest first by default),
+    /// after the bonsai `start_after`. Sorting by price needs a price filter for its asset.
+    /// Prices are list prices: the asking price of an offer, or the price set for the
+    /// bonsai, before the pricing mode and the sale events; see `GetCurrentPrice`
+    SearchBonsais {
+        #[serde(default)]
+        filter: BonsaiFilter,
+        order: Option<SearchOrder>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// the first gardeners of the leaderboard
+    TopGardeners {
+        by: Leaderboard,
+        limit: Option<u32>,
+    },
+    /// sales volume, floor and last sale price, and number of gardeners
+    GetStats {},
+    /// how many bonsais were minted, sold, are owned and were cut
+    GetSupply {},
+    /// the history of the bonsai, oldest first, after the entry with the index `start_after`
+    GetBonsaiHistory {
+        b_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// the tombstone of a cut bonsai
+    GetTombstone {
+        b_id: u64,
+    },
+    /// the random bonsai paid by the address and not revealed yet
+    GetRandomPurchase {
+        address: HumanAddr,
+    },
+    HasRole {
+        address: HumanAddr,
+        role: Role,
+    },
+    /// only built with the `invariants` feature: check that every bonsai has exactly one owner
+    #[cfg(feature = "invariants")]
+    CheckInvariants {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: HumanAddr,
+    pub default_prices: Vec<Price>,
+    pub cw20_whitelist: Vec<HumanAddr>,
+    pub fee_collector: HumanAddr,
+    pub protocol_fee_bps: u64,
+    pub royalty_bps: u64,
+    pub pricing: PricingMode,
+    pub presale: Option<Presale>,
+    pub max_per_wallet: Option<u64>,
+    pub max_initial_inventory: u64,
+    pub commit_reveal: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AllGardenersResponse {
     pub gardeners: Vec<Gardener>,
 }
+
+/// how a resale price of a bonsai is split
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltyInfoResponse {
+    pub fee_collector: HumanAddr,
+    pub protocol_fee: Uint128,
+    pub royalty_recipient: Option<HumanAddr>,
+    pub royalty: Uint128,
+    // what's left to the seller
+    pub seller_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentPriceResponse {
+    pub prices: Vec<Price>,
+    // discount of the active sale event, already applied to the prices
+    pub discount_percent: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleEventsResponse {
+    pub events: Vec<SaleEvent>,
+    pub active_discount_percent: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PresaleStatusResponse {
+    pub presale_active: bool,
+    pub public_sale_height: Option<u64>,
+    pub allowlisted: bool,
+    // bonsais bought from the shop by the address
+    pub purchased: u64,
+    pub max_per_wallet: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SupplyResponse {
+    // ids ever taken, by the shop, crossing or propagation
+    pub total_supply: u64,
+    // bonsais whose record has been created, bred ones included
+    pub minted: u64,
+    // bonsais sold by the shop
+    pub sold: u64,
+    // bonsais held by gardeners
+    pub owned: u64,
+    // bonsais cut
+    pub burned: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BonsaiHistoryResponse {
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// the prices in an asset, the native denom or the cw20 contract address, within the bounds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceFilter {
+    pub asset: String,
+    pub min: Option<Uint128>,
+    pub max: Option<Uint128>,
+}
+
+/// what the searched bonsais must match, the bounds are included and
+/// a filter left out matches every bonsai
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct BonsaiFilter {
+    pub price: Option<PriceFilter>,
+    pub min_birth_date: Option<u64>,
+    pub max_birth_date: Option<u64>,
+    pub owner: Option<HumanAddr>,
+    // bonsais in the shop or offered by their owners
+    pub for_sale: Option<bool>,
+    pub species: Option<Species>,
+    pub rarity: Option<Rarity>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchOrder {
+    Oldest,
+    Newest,
+    Cheapest,
+    Priciest,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FoundBonsai {
+    pub bonsai: Bonsai,
+    // none when the bonsai is in the shop
+    pub owner: Option<HumanAddr>,
+    pub for_sale: bool,
+    // the list prices the search filters and sorts by, none when not for sale
+    pub prices: Vec<Price>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SearchBonsaisResponse {
+    pub bonsais: Vec<FoundBonsai>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RankedGardener {
+    pub address: HumanAddr,
+    pub scores: GardenerScores,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TopGardenersResponse {
+    pub gardeners: Vec<RankedGardener>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseStatusResponse {
+    // none when the contract isn't paused
+    pub scope: Option<PauseScope>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HasRoleResponse {
+    pub has_role: bool,
+}
+
+#[cfg(any(test, feature = "invariants"))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvariantsResponse {
+    pub valid: bool,
+    /// ids of the bonsais held by more than one owner
+    pub duplicated: Vec<u64>,
+}
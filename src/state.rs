@@ -1,32 +1,684 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Coin, Storage};
+use cosmwasm_std::{CanonicalAddr, Coin, HumanAddr, Order, StdError, StdResult, Storage, Uint128};
 use cosmwasm_storage::{
     bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
     Singleton,
 };
+use cw20::Cw20CoinHuman;
+use sha2::{Digest, Sha256};
+
+use crate::errors::MyCustomError;
 
 pub static BONSAI_KEY: &[u8] = b"bonsai";
 pub static GARDENERS_KEY: &[u8] = b"gardener";
+pub static CW20_WHITELIST_KEY: &[u8] = b"cw20_whitelist";
+pub static CONFIG_KEY: &[u8] = b"config";
+pub static SALE_OFFERS_KEY: &[u8] = b"sale_offer";
+pub static SOLD_KEY: &[u8] = b"sold";
+pub static SALE_EVENTS_KEY: &[u8] = b"sale_events";
+pub static ALLOWLIST_KEY: &[u8] = b"allowlist";
+pub static PAUSED_KEY: &[u8] = b"paused";
+pub static ROLES_KEY: &[u8] = b"roles";
+pub static INVENTORY_KEY: &[u8] = b"inventory";
+pub static GROWN_KEY: &[u8] = b"grown";
+pub static VIRTUAL_SLOTS_KEY: &[u8] = b"virtual_slot";
+pub static VIRTUAL_POSITIONS_KEY: &[u8] = b"virtual_position";
+pub static SEED_COMMITMENTS_KEY: &[u8] = b"seed_commitment";
+pub static LAST_BLOCK_KEY: &[u8] = b"last_block";
+pub static BLOCK_SEEDS_KEY: &[u8] = b"block_seed";
+pub static RANDOM_PURCHASES_KEY: &[u8] = b"random_purchase";
+pub static RANDOM_DRAWS_KEY: &[u8] = b"random_draw";
+pub static LINEAGE_KEY: &[u8] = b"lineage";
+pub static COOLDOWNS_KEY: &[u8] = b"cooldown";
+pub static BURNED_KEY: &[u8] = b"burned";
+pub static TOMBSTONES_KEY: &[u8] = b"tombstone";
+pub static HISTORY_KEY: &[u8] = b"history";
+pub static STATS_KEY: &[u8] = b"stats";
+pub static SCORES_KEY: &[u8] = b"scores";
+pub static RANKINGS_KEY: &[u8] = b"rankings";
+pub static SEARCH_ENTRIES_KEY: &[u8] = b"search_entry";
+pub static SEARCH_INDEX_KEY: &[u8] = b"search_index";
+
+/// fees and royalties are expressed in basis points of the sale price
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// bonsais grown up front when the init doesn't say otherwise
+pub const DEFAULT_MAX_INITIAL_INVENTORY: u64 = 100;
+/// upper bound of the configurable inventory grown in a single transaction
+pub const MAX_INITIAL_INVENTORY: u64 = 1_000;
+
+/// age, in blocks, from which a bonsai can be crossed
+pub const MATURITY_BLOCKS: u64 = 100;
+/// blocks a bonsai has to rest after being crossed
+pub const CROSS_COOLDOWN_BLOCKS: u64 = 200;
+/// blocks the buyer of a bonsai has to commit to a secret and reveal its traits,
+/// after that anyone can reveal them without the secret
+pub const REVEAL_DEADLINE_BLOCKS: u64 = 1_000;
+/// random bonsais that can be paid in a block, as a later transaction draws them all
+pub const MAX_RANDOM_DRAWS_PER_BLOCK: u64 = 10;
+
+/// highest step of the pricing curves: a sale can at most double the price
+pub const MAX_STEP_BPS: u64 = BPS_DENOMINATOR;
+/// scale of the multiplier of the exponential curve
+const CURVE_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// how the price of the bonsais still in the shop evolves
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingMode {
+    /// every bonsai is sold at its own price
+    Fixed {},
+    /// every bonsai sold by the shop raises the price by `step_bps` of the base price
+    LinearCurve { step_bps: u64 },
+    /// every bonsai sold by the shop raises the price by `step_bps` of the previous price
+    ExponentialCurve { step_bps: u64 },
+    /// the price drops by `decay_bps` of the base price for every block since
+    /// the bonsai birth, without going below `floor_bps` of the base price
+    DutchAuction { decay_bps: u64, floor_bps: u64 },
+}
+
+impl PricingMode {
+    /// the price of a shop bonsai given how many bonsais the shop already sold
+    /// and how many blocks passed since the bonsai birth
+    pub fn current_amount(
+        &self,
+        base: Uint128,
+        sold: u64,
+        age: u64,
+    ) -> Result<Uint128, MyCustomError> {
+        let bps = BPS_DENOMINATOR as u128;
+        let amount = match self {
+            PricingMode::Fixed {} => Some(base.u128()),
+            PricingMode::LinearCurve { step_bps } => {
+                mul_div(base.u128(), *step_bps as u128 * sold as u128, bps)
+                    .and_then(|step| base.u128().checked_add(step))
+            }
+            PricingMode::ExponentialCurve { step_bps } => {
+                // exponentiation by squaring of the multiplier of a single sale
+                let mut multiplier = Some(CURVE_SCALE);
+                let mut power = mul_div(CURVE_SCALE, bps + *step_bps as u128, bps);
+                let mut exponent = sold;
+                while exponent > 0 {
+                    if exponent & 1 == 1 {
+                        multiplier = multiplier
+                            .zip(power)
+                            .and_then(|(m, p)| mul_div(m, p, CURVE_SCALE));
+                    }
+                    exponent >>= 1;
+                    if exponent > 0 {
+                        power = power.and_then(|p| mul_div(p, p, CURVE_SCALE));
+                    }
+                }
+                multiplier.and_then(|m| mul_div(base.u128(), m, CURVE_SCALE))
+            }
+            PricingMode::DutchAuction {
+                decay_bps,
+                floor_bps,
+            } => {
+                let floor = mul_div(base.u128(), *floor_bps as u128, bps);
+                // a decay that doesn't fit is beyond the base price anyway
+                let decay = mul_div(base.u128(), *decay_bps as u128 * age as u128, bps)
+                    .unwrap_or(u128::MAX);
+                floor.map(|floor| base.u128().saturating_sub(decay).max(floor))
+            }
+        };
+        amount.map(Uint128).ok_or(MyCustomError::PriceOverflow {})
+    }
+}
+
+/// `a * b / c` rounded down, computed on 256 bits so that only a result
+/// that doesn't fit in 128 bits, or a zero `c`, gives none
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let low = a_lo * b_lo;
+    let cross_a = a_hi * b_lo;
+    let cross_b = a_lo * b_hi;
+    let middle = (low >> 64) + (cross_a & MASK) + (cross_b & MASK);
+    let lo = (low & MASK) | (middle << 64);
+    let hi = a_hi * b_hi + (cross_a >> 64) + (cross_b >> 64) + (middle >> 64);
+    if hi == 0 {
+        return Some(lo / c);
+    }
+    if hi >= c {
+        return None;
+    }
+
+    // long division of the 256 bits product, the remainder always stays below `c`
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1 << bit;
+        }
+    }
+    Some(quotient)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    // allowed to change the config and to grant roles
+    pub owner: CanonicalAddr,
+    // prices of the bonsais grown at init and, by default, of the ones grown later
+    pub default_prices: Vec<Price>,
+    // address receiving the protocol fee taken on every sale
+    pub fee_collector: CanonicalAddr,
+    pub protocol_fee_bps: u64,
+    pub pricing: PricingMode,
+    pub presale: Option<Presale>,
+    // how many bonsais a single gardener can buy from the shop
+    pub max_per_wallet: Option<u64>,
+    // royalty given to every grown bonsai
+    pub royalty: Option<Royalty>,
+    // bonsais grown up front or in a single transaction, the rest of the inventory is virtual
+    pub max_initial_inventory: u64,
+    // the traits of the grown bonsais are revealed by their owners with a committed secret
+    pub commit_reveal: bool,
+}
+
+impl Config {
+    pub fn protocol_fee(&self, sale_price: Uint128) -> Uint128 {
+        sale_price.multiply_ratio(self.protocol_fee_bps, BPS_DENOMINATOR)
+    }
+}
+
+/// return a writable contract config
+pub fn config_store(storage: &mut dyn Storage) -> Singleton<Config> {
+    singleton(storage, CONFIG_KEY)
+}
+
+/// return a read-only contract config
+pub fn config_store_read(storage: &dyn Storage) -> ReadonlySingleton<Config> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+/// the operations the owner can delegate to other addresses
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// grow new bonsais in the shop
+    Minter,
+    /// change the shop prices and schedule sale events
+    PriceManager,
+    /// pause and unpause the contract
+    Pauser,
+}
+
+/// return the writable roles of every address
+pub fn roles_store(storage: &mut dyn Storage) -> Bucket<Vec<Role>> {
+    bucket(storage, ROLES_KEY)
+}
+
+/// return the read-only roles of every address
+pub fn roles_store_read(storage: &dyn Storage) -> ReadonlyBucket<Vec<Role>> {
+    bucket_read(storage, ROLES_KEY)
+}
+
+/// the operations frozen by the owner in case of emergency
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseScope {
+    /// buying bonsais, from the shop or from other gardeners
+    Purchases,
+    /// offering bonsais to other gardeners and buying the offered ones
+    Transfers,
+    /// every operation but the owner ones
+    All,
+}
+
+/// return the writable pause scope, removed when the contract is unpaused
+pub fn paused_store(storage: &mut dyn Storage) -> Singleton<PauseScope> {
+    singleton(storage, PAUSED_KEY)
+}
+
+/// return the read-only pause scope
+pub fn paused_store_read(storage: &dyn Storage) -> ReadonlySingleton<PauseScope> {
+    singleton_read(storage, PAUSED_KEY)
+}
+
+/// until `public_sale_height` only the allowlisted gardeners can buy from the shop
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Presale {
+    pub public_sale_height: u64,
+    // hex encoded merkle root of the allowed addresses, who join the allowlist with a proof
+    pub merkle_root: Option<String>,
+}
+
+impl Presale {
+    pub fn is_active(&self, height: u64) -> bool {
+        height < self.public_sale_height
+    }
+
+    /// whether the proof links the address to the merkle root. Leaves are the sha256 of
+    /// the addresses and every pair of nodes is hashed in ascending order
+    pub fn verify(&self, address: &HumanAddr, proof: &[String]) -> StdResult<bool> {
+        let root = match &self.merkle_root {
+            Some(root) => decode_hash(root)?,
+            None => return Ok(false),
+        };
+
+        let mut hash: [u8; 32] = Sha256::digest(address.as_str().as_bytes()).into();
+        for node in proof.iter() {
+            let node = decode_hash(node)?;
+            let (first, second) = if hash <= node {
+                (hash, node)
+            } else {
+                (node, hash)
+            };
+            hash = Sha256::new().chain(first).chain(second).finalize().into();
+        }
+        Ok(hash == root)
+    }
+}
+
+/// decode a hex encoded sha256 hash
+pub fn decode_hash(hash: &str) -> StdResult<[u8; 32]> {
+    let mut decoded = [0u8; 32];
+    hex::decode_to_slice(hash, &mut decoded)
+        .map_err(|_| StdError::generic_err(format!("Invalid sha256 hash: {}", hash)))?;
+    Ok(decoded)
+}
+
+/// the traits of a bought bonsai waiting to be revealed. They are drawn from the
+/// secret and from the first block after `height`, unknown when committing
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeedCommitment {
+    // hex encoded sha256 of the secret, none until the owner commits
+    pub commitment: Option<String>,
+    // block of the commitment, or of the purchase before it
+    pub height: u64,
+    // from this height anyone can reveal the traits, without the secret
+    pub deadline: u64,
+}
+
+impl SeedCommitment {
+    /// whether the secret matches the commitment
+    pub fn is_revealed_by(&self, secret: &str) -> StdResult<bool> {
+        let commitment = match &self.commitment {
+            Some(commitment) => commitment,
+            None => return Ok(false),
+        };
+        let hash: [u8; 32] = Sha256::digest(secret.as_bytes()).into();
+        Ok(hash == decode_hash(commitment)?)
+    }
+}
+
+/// return the writable seed commitments, keyed by bonsai id
+pub fn seed_commitments_store(storage: &mut dyn Storage) -> Bucket<SeedCommitment> {
+    bucket(storage, SEED_COMMITMENTS_KEY)
+}
+
+/// return the read-only seed commitments, keyed by bonsai id
+pub fn seed_commitments_store_read(storage: &dyn Storage) -> ReadonlyBucket<SeedCommitment> {
+    bucket_read(storage, SEED_COMMITMENTS_KEY)
+}
+
+/// the first block the contract ran in after a given height
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockSeed {
+    pub height: u64,
+    pub time: u64,
+    pub time_nanos: u64,
+}
+
+/// return the writable height of the last block the contract ran in
+pub fn last_block_store(storage: &mut dyn Storage) -> Singleton<u64> {
+    singleton(storage, LAST_BLOCK_KEY)
+}
+
+/// return the read-only height of the last block the contract ran in
+pub fn last_block_store_read(storage: &dyn Storage) -> ReadonlySingleton<u64> {
+    singleton_read(storage, LAST_BLOCK_KEY)
+}
+
+/// return the writable block seeds, keyed by the height they follow
+pub fn block_seeds_store(storage: &mut dyn Storage) -> Bucket<BlockSeed> {
+    bucket(storage, BLOCK_SEEDS_KEY)
+}
+
+/// return the read-only block seeds, keyed by the height they follow
+pub fn block_seeds_store_read(storage: &dyn Storage) -> ReadonlyBucket<BlockSeed> {
+    bucket_read(storage, BLOCK_SEEDS_KEY)
+}
+
+/// a random bonsai paid in a block, drawn from the shop by the first transaction of a later block
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RandomPurchase {
+    pub price: Price,
+    pub height: u64,
+}
+
+/// return the writable random purchases waiting to be drawn, keyed by buyer address
+pub fn random_purchases_store(storage: &mut dyn Storage) -> Bucket<RandomPurchase> {
+    bucket(storage, RANDOM_PURCHASES_KEY)
+}
+
+/// return the read-only random purchases waiting to be drawn, keyed by buyer address
+pub fn random_purchases_store_read(storage: &dyn Storage) -> ReadonlyBucket<RandomPurchase> {
+    bucket_read(storage, RANDOM_PURCHASES_KEY)
+}
+
+/// return the writable buyers of random bonsais, keyed by the height they paid at
+pub fn random_draws_store(storage: &mut dyn Storage) -> Bucket<Vec<CanonicalAddr>> {
+    bucket(storage, RANDOM_DRAWS_KEY)
+}
+
+/// return the read-only buyers of random bonsais, keyed by the height they paid at
+pub fn random_draws_store_read(storage: &dyn Storage) -> ReadonlyBucket<Vec<CanonicalAddr>> {
+    bucket_read(storage, RANDOM_DRAWS_KEY)
+}
+
+/// return the writable presale allowlist
+pub fn allowlist_store(storage: &mut dyn Storage) -> Bucket<bool> {
+    bucket(storage, ALLOWLIST_KEY)
+}
+
+/// return the read-only presale allowlist
+pub fn allowlist_store_read(storage: &dyn Storage) -> ReadonlyBucket<bool> {
+    bucket_read(storage, ALLOWLIST_KEY)
+}
+
+/// a discount on every shop bonsai from `start_height` until right before `end_height`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleEvent {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub discount_percent: u64,
+}
+
+impl SaleEvent {
+    pub fn is_active(&self, height: u64) -> bool {
+        self.start_height <= height && height < self.end_height
+    }
+}
+
+/// the highest discount among the sale events active at the given height
+pub fn active_discount(events: &[SaleEvent], height: u64) -> u64 {
+    events
+        .iter()
+        .filter(|event| event.is_active(height))
+        .map(|event| event.discount_percent)
+        .max()
+        .unwrap_or(0)
+}
+
+/// return the writable sale events of the shop
+pub fn sale_events_store(storage: &mut dyn Storage) -> Singleton<Vec<SaleEvent>> {
+    singleton(storage, SALE_EVENTS_KEY)
+}
+
+/// return the read-only sale events of the shop
+pub fn sale_events_store_read(storage: &dyn Storage) -> ReadonlySingleton<Vec<SaleEvent>> {
+    singleton_read(storage, SALE_EVENTS_KEY)
+}
+
+/// an asset a bonsai can be paid with: a native coin or an amount of a whitelisted cw20 token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Price {
+    Native(Coin),
+    Cw20(Cw20CoinHuman),
+}
+
+impl Price {
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            Price::Native(coin) => coin.amount,
+            Price::Cw20(coin) => coin.amount,
+        }
+    }
+
+    /// whether the payment is made with the same asset and covers this price
+    pub fn is_covered_by(&self, payment: &Price) -> bool {
+        match (self, payment) {
+            (Price::Native(price), Price::Native(coin)) => {
+                coin.denom == price.denom && coin.amount >= price.amount
+            }
+            (Price::Cw20(price), Price::Cw20(coin)) => {
+                coin.address == price.address && coin.amount >= price.amount
+            }
+            _ => false,
+        }
+    }
+
+    /// the native denom or the cw20 contract address
+    pub fn asset(&self) -> &str {
+        match self {
+            Price::Native(coin) => &coin.denom,
+            Price::Cw20(coin) => coin.address.as_str(),
+        }
+    }
+
+    pub fn is_same_asset(&self, other: &Price) -> bool {
+        match (self, other) {
+            (Price::Native(a), Price::Native(b)) => a.denom == b.denom,
+            (Price::Cw20(a), Price::Cw20(b)) => a.address == b.address,
+            _ => false,
+        }
+    }
+
+    /// the same asset of this price with a different amount
+    pub fn with_amount(&self, amount: Uint128) -> Price {
+        match self {
+            Price::Native(coin) => Price::Native(Coin {
+                denom: coin.denom.clone(),
+                amount,
+            }),
+            Price::Cw20(coin) => Price::Cw20(Cw20CoinHuman {
+                address: coin.address.clone(),
+                amount,
+            }),
+        }
+    }
+}
+
+impl From<Coin> for Price {
+    fn from(coin: Coin) -> Price {
+        Price::Native(coin)
+    }
+}
+
+/// share of every resale paid back to the creator of a bonsai
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub recipient: CanonicalAddr,
+    pub bps: u64,
+}
+
+impl Royalty {
+    pub fn amount(&self, sale_price: Uint128) -> Uint128 {
+        sale_price.multiply_ratio(self.bps, BPS_DENOMINATOR)
+    }
+}
+
+/// hash based pseudo random generator: every number is taken from
+/// the sha256 of the seed and of a counter
+pub struct Prng {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl Prng {
+    /// seed the generator with the hash of all the given parts
+    pub fn new(parts: &[&[u8]]) -> Prng {
+        let seed = parts
+            .iter()
+            .fold(Sha256::new(), |hasher, part| hasher.chain(part))
+            .finalize()
+            .into();
+        Prng { seed, counter: 0 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let hash = Sha256::new()
+            .chain(self.seed)
+            .chain(self.counter.to_be_bytes())
+            .finalize();
+        self.counter += 1;
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash[..8]);
+        u64::from_be_bytes(bytes)
+    }
+
+    /// a number from 0 up to `bound` (excluded)
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Species {
+    Juniper,
+    Pine,
+    Maple,
+    Ficus,
+    Elm,
+    Azalea,
+}
+
+pub const SPECIES: [Species; 6] = [
+    Species::Juniper,
+    Species::Pine,
+    Species::Maple,
+    Species::Ficus,
+    Species::Elm,
+    Species::Azalea,
+];
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
+}
+
+impl Rarity {
+    /// the next rarity, legendary being the highest
+    pub fn next(self) -> Rarity {
+        match self {
+            Rarity::Common => Rarity::Uncommon,
+            Rarity::Uncommon => Rarity::Rare,
+            Rarity::Rare | Rarity::Legendary => Rarity::Legendary,
+        }
+    }
+
+    /// the previous rarity, common being the lowest
+    pub fn previous(self) -> Rarity {
+        match self {
+            Rarity::Common | Rarity::Uncommon => Rarity::Common,
+            Rarity::Rare => Rarity::Uncommon,
+            Rarity::Legendary => Rarity::Rare,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Traits {
+    pub species: Species,
+    pub rarity: Rarity,
+}
+
+impl Traits {
+    /// every species is as likely, while out of 100 bonsais 60 are common,
+    /// 25 uncommon, 12 rare and 3 legendary
+    pub fn random(prng: &mut Prng) -> Traits {
+        let species = SPECIES[prng.below(SPECIES.len() as u64) as usize];
+        let rarity = match prng.below(100) {
+            0..=59 => Rarity::Common,
+            60..=84 => Rarity::Uncommon,
+            85..=96 => Rarity::Rare,
+            _ => Rarity::Legendary,
+        };
+        Traits { species, rarity }
+    }
+
+    /// the traits of a bonsai not revealed in time: any species, but common
+    pub fn forfeited(prng: &mut Prng) -> Traits {
+        let species = SPECIES[prng.below(SPECIES.len() as u64) as usize];
+        Traits {
+            species,
+            rarity: Rarity::Common,
+        }
+    }
+
+    /// the child takes the species of one of its parents and the lowest of their rarities.
+    /// Parents of the same rarity have one chance out of 10 to give a rarer child
+    pub fn cross(a: &Traits, b: &Traits, prng: &mut Prng) -> Traits {
+        let species = if prng.below(2) == 0 {
+            a.species
+        } else {
+            b.species
+        };
+        let mut rarity = a.rarity.min(b.rarity);
+        if a.rarity == b.rarity && prng.below(10) == 0 {
+            rarity = rarity.next();
+        }
+        Traits { species, rarity }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Bonsai {
     pub id: u64,
     // block height at which the bonsai was created
     pub birth_date: u64,
-    pub price: Coin,
+    // the bonsai can be paid with any one of these prices
+    pub prices: Vec<Price>,
+    pub royalty: Option<Royalty>,
+    // not revealed yet when the shop uses commit reveal
+    #[serde(default)]
+    pub traits: Option<Traits>,
 }
 
 impl Bonsai {
     // not a method but an associate function
-    pub fn new(id: u64, birth_date: u64, price: Coin) -> Bonsai {
+    pub fn new(id: u64, birth_date: u64, prices: Vec<Price>) -> Bonsai {
         Bonsai {
             id,
             birth_date,
-            price,
+            prices,
+            royalty: None,
+            traits: None,
         }
     }
+
+    /// the accepted prices of the bonsai while it's in the shop
+    pub fn shop_prices(
+        &self,
+        pricing: &PricingMode,
+        sold: u64,
+        height: u64,
+    ) -> Result<Vec<Price>, MyCustomError> {
+        let age = height.saturating_sub(self.birth_date);
+        self.prices
+            .iter()
+            .map(|price| {
+                let amount = pricing.current_amount(price.amount(), sold, age)?;
+                Ok(price.with_amount(amount))
+            })
+            .collect()
+    }
+
+    /// return the first accepted price that is covered by one of the given payments
+    pub fn matching_price(&self, payments: &[Price]) -> Option<&Price> {
+        self.prices
+            .iter()
+            .find(|price| payments.iter().any(|payment| price.is_covered_by(payment)))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
@@ -36,17 +688,386 @@ pub struct BonsaiList {
 
 impl BonsaiList {
     /// grow some bonsais from a given number, watering each one of those
-    pub fn grow_bonsais(number: u64, birth_date: u64, price: Coin) -> BonsaiList {
-        let mut i = 0;
-        let mut bonsais: Vec<Bonsai> = Vec::with_capacity(number as usize);
-        while i < number {
-            bonsais.push(Bonsai::new(i, birth_date, price.clone()));
-            i += 1;
+    pub fn grow_bonsais(
+        number: u64,
+        birth_date: u64,
+        prices: Vec<Price>,
+        royalty: Option<Royalty>,
+    ) -> BonsaiList {
+        let mut bonsai_list = BonsaiList {
+            bonsais: Vec::with_capacity(number as usize),
+        };
+        bonsai_list.grow(0, number, birth_date, prices, royalty);
+        bonsai_list
+    }
+
+    /// add `number` new bonsais to the list, numbered from `first_id`
+    pub fn grow(
+        &mut self,
+        first_id: u64,
+        number: u64,
+        birth_date: u64,
+        prices: Vec<Price>,
+        royalty: Option<Royalty>,
+    ) {
+        for id in first_id..first_id + number {
+            let mut bonsai = Bonsai::new(id, birth_date, prices.clone());
+            bonsai.royalty = royalty.clone();
+            self.bonsais.push(bonsai);
         }
-        BonsaiList { bonsais }
     }
 }
 
+/// the shop inventory. Only part of it is grown up front, the records of
+/// the virtual bonsais are created when they are bought
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Inventory {
+    // ids ever taken, by the shop, crossing or propagation, which is also the next bonsai id
+    pub total_supply: u64,
+    // bonsais whose record has been created
+    pub minted: u64,
+    // the ids from `first_virtual_id` to `end_virtual_id` (excluded) are sold before being grown
+    pub first_virtual_id: u64,
+    pub end_virtual_id: u64,
+    // virtual bonsais whose record has been created
+    pub virtual_grown: u64,
+    // the height the virtual bonsais were put in the shop, which is their birth date
+    pub virtual_birth_date: u64,
+}
+
+impl Inventory {
+    pub fn is_virtual(&self, id: u64) -> bool {
+        self.first_virtual_id <= id && id < self.end_virtual_id
+    }
+
+    /// virtual bonsais the shop can still sell
+    pub fn virtual_left(&self) -> u64 {
+        self.end_virtual_id - self.first_virtual_id - self.virtual_grown
+    }
+}
+
+/// the parents a bonsai was bred from, if any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Lineage {
+    pub parents: Vec<u64>,
+    // 0 for the bonsais grown by the shop, one more than the oldest parent otherwise
+    pub generation: u64,
+}
+
+/// return the writable lineages of the bred bonsais, keyed by bonsai id
+pub fn lineage_store(storage: &mut dyn Storage) -> Bucket<Lineage> {
+    bucket(storage, LINEAGE_KEY)
+}
+
+/// return the read-only lineages of the bred bonsais, keyed by bonsai id
+pub fn lineage_store_read(storage: &dyn Storage) -> ReadonlyBucket<Lineage> {
+    bucket_read(storage, LINEAGE_KEY)
+}
+
+/// return the writable heights until which bonsais can't be crossed, keyed by bonsai id
+pub fn cooldowns_store(storage: &mut dyn Storage) -> Bucket<u64> {
+    bucket(storage, COOLDOWNS_KEY)
+}
+
+/// return the read-only heights until which bonsais can't be crossed, keyed by bonsai id
+pub fn cooldowns_store_read(storage: &dyn Storage) -> ReadonlyBucket<u64> {
+    bucket_read(storage, COOLDOWNS_KEY)
+}
+
+/// return the writable shop inventory
+pub fn inventory_store(storage: &mut dyn Storage) -> Singleton<Inventory> {
+    singleton(storage, INVENTORY_KEY)
+}
+
+/// return the read-only shop inventory
+pub fn inventory_store_read(storage: &dyn Storage) -> ReadonlySingleton<Inventory> {
+    singleton_read(storage, INVENTORY_KEY)
+}
+
+/// return a writable bucket of the virtual bonsais already grown, keyed by bonsai id
+pub fn grown_store(storage: &mut dyn Storage) -> Bucket<bool> {
+    bucket(storage, GROWN_KEY)
+}
+
+/// return a read-only bucket of the virtual bonsais already grown, keyed by bonsai id
+pub fn grown_store_read(storage: &dyn Storage) -> ReadonlyBucket<bool> {
+    bucket_read(storage, GROWN_KEY)
+}
+
+/// return the writable virtual ids not grown yet, keyed by slot. The slots from 0 to
+/// `virtual_left` hold them, a slot without an entry holds `first_virtual_id + slot`
+pub fn virtual_slots_store(storage: &mut dyn Storage) -> Bucket<u64> {
+    bucket(storage, VIRTUAL_SLOTS_KEY)
+}
+
+/// return the read-only virtual ids not grown yet, keyed by slot
+pub fn virtual_slots_store_read(storage: &dyn Storage) -> ReadonlyBucket<u64> {
+    bucket_read(storage, VIRTUAL_SLOTS_KEY)
+}
+
+/// return the writable slots of the virtual ids moved from their own, keyed by id
+pub fn virtual_positions_store(storage: &mut dyn Storage) -> Bucket<u64> {
+    bucket(storage, VIRTUAL_POSITIONS_KEY)
+}
+
+/// return the read-only slots of the virtual ids moved from their own, keyed by id
+pub fn virtual_positions_store_read(storage: &dyn Storage) -> ReadonlyBucket<u64> {
+    bucket_read(storage, VIRTUAL_POSITIONS_KEY)
+}
+
+/// return the writable number of bonsais sold by the shop
+pub fn sold_store(storage: &mut dyn Storage) -> Singleton<u64> {
+    singleton(storage, SOLD_KEY)
+}
+
+/// return the read-only number of bonsais sold by the shop
+pub fn sold_store_read(storage: &dyn Storage) -> ReadonlySingleton<u64> {
+    singleton_read(storage, SOLD_KEY)
+}
+
+/// return the writable number of bonsais cut
+pub fn burned_store(storage: &mut dyn Storage) -> Singleton<u64> {
+    singleton(storage, BURNED_KEY)
+}
+
+/// return the read-only number of bonsais cut
+pub fn burned_store_read(storage: &dyn Storage) -> ReadonlySingleton<u64> {
+    singleton_read(storage, BURNED_KEY)
+}
+
+/// what is left of a cut bonsai
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Tombstone {
+    pub id: u64,
+    pub last_owner: CanonicalAddr,
+    pub cut_height: u64,
+}
+
+/// return the writable tombstones of the cut bonsais, keyed by bonsai id
+pub fn tombstones_store(storage: &mut dyn Storage) -> Bucket<Tombstone> {
+    bucket(storage, TOMBSTONES_KEY)
+}
+
+/// return the read-only tombstones of the cut bonsais, keyed by bonsai id
+pub fn tombstones_store_read(storage: &dyn Storage) -> ReadonlyBucket<Tombstone> {
+    bucket_read(storage, TOMBSTONES_KEY)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    /// bought from the shop
+    Buy,
+    /// offered by its owner to a buyer
+    Sell,
+    /// bought from its previous owner
+    Transfer,
+    Cut,
+}
+
+/// something that happened to a bonsai. The shop is neither `from` nor `to`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoryEntry {
+    // position of the entry in the history of the bonsai
+    pub index: u64,
+    pub kind: HistoryKind,
+    pub from: Option<CanonicalAddr>,
+    pub to: Option<CanonicalAddr>,
+    pub price: Option<Price>,
+    pub height: u64,
+}
+
+/// return the writable history of a bonsai, keyed by entry index
+pub fn history_store(storage: &mut dyn Storage, b_id: u64) -> Bucket<HistoryEntry> {
+    Bucket::multilevel(storage, &[HISTORY_KEY, &b_id.to_be_bytes()])
+}
+
+/// return the read-only history of a bonsai, keyed by entry index
+pub fn history_store_read(storage: &dyn Storage, b_id: u64) -> ReadonlyBucket<HistoryEntry> {
+    ReadonlyBucket::multilevel(storage, &[HISTORY_KEY, &b_id.to_be_bytes()])
+}
+
+/// append an entry to the history of a bonsai, after the last one
+pub fn record_history(
+    storage: &mut dyn Storage,
+    b_id: u64,
+    kind: HistoryKind,
+    from: Option<CanonicalAddr>,
+    to: Option<CanonicalAddr>,
+    price: Option<Price>,
+    height: u64,
+) -> StdResult<()> {
+    let index = match history_store_read(storage, b_id)
+        .range(None, None, Order::Descending)
+        .next()
+    {
+        Some(last) => last?.1.index + 1,
+        None => 0,
+    };
+    history_store(storage, b_id).save(
+        &index.to_be_bytes(),
+        &HistoryEntry {
+            index,
+            kind,
+            from,
+            to,
+            price,
+            height,
+        },
+    )
+}
+
+/// aggregates of the sales made by the shop and between gardeners
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Stats {
+    pub sales: u64,
+    // amount paid for bonsais in each asset
+    pub volume: Vec<Price>,
+    // lowest price a bonsai was sold for in each asset
+    pub floor: Vec<Price>,
+    pub last_sale: Option<Price>,
+    pub gardeners: u64,
+}
+
+impl Stats {
+    pub fn record_sale(&mut self, price: &Price) {
+        self.sales += 1;
+        match self.volume.iter_mut().find(|v| v.is_same_asset(price)) {
+            Some(volume) => *volume = volume.with_amount(volume.amount() + price.amount()),
+            None => self.volume.push(price.clone()),
+        }
+        match self.floor.iter_mut().find(|f| f.is_same_asset(price)) {
+            Some(floor) if floor.amount() <= price.amount() => {}
+            Some(floor) => *floor = price.clone(),
+            None => self.floor.push(price.clone()),
+        }
+        self.last_sale = Some(price.clone());
+    }
+}
+
+/// return the writable sale statistics
+pub fn stats_store(storage: &mut dyn Storage) -> Singleton<Stats> {
+    singleton(storage, STATS_KEY)
+}
+
+/// return the read-only sale statistics
+pub fn stats_store_read(storage: &dyn Storage) -> ReadonlySingleton<Stats> {
+    singleton_read(storage, STATS_KEY)
+}
+
+/// what gardeners are ranked by
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Leaderboard {
+    /// most bonsais first
+    BonsaiCount,
+    /// most spent buying bonsais first, in the given native denom or cw20 address
+    TotalSpent { asset: String },
+    /// oldest bonsai first, gardeners without bonsais are left out
+    OldestTree,
+}
+
+/// the scores of a gardener in the leaderboards
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct GardenerScores {
+    pub bonsai_count: u64,
+    // amount spent buying bonsais in each asset
+    pub spent: Vec<Price>,
+    // birth date of the oldest bonsai
+    pub oldest_tree: Option<u64>,
+}
+
+impl GardenerScores {
+    /// the leaderboards the gardener appears in, with the key ordering it in each of them
+    fn ranking_keys(&self, address: &CanonicalAddr) -> Vec<(Leaderboard, Vec<u8>)> {
+        let key = |score: &[u8]| [score, address.as_slice()].concat();
+        let mut keys = vec![(
+            Leaderboard::BonsaiCount,
+            key(&self.bonsai_count.to_be_bytes()),
+        )];
+        for spent in self.spent.iter() {
+            keys.push((
+                Leaderboard::TotalSpent {
+                    asset: spent.asset().to_string(),
+                },
+                key(&spent.amount().u128().to_be_bytes()),
+            ));
+        }
+        if let Some(birth_date) = self.oldest_tree {
+            keys.push((Leaderboard::OldestTree, key(&birth_date.to_be_bytes())));
+        }
+        keys
+    }
+}
+
+/// return the writable scores of the gardeners, keyed by address
+pub fn scores_store(storage: &mut dyn Storage) -> Bucket<GardenerScores> {
+    bucket(storage, SCORES_KEY)
+}
+
+/// return the read-only scores of the gardeners, keyed by address
+pub fn scores_store_read(storage: &dyn Storage) -> ReadonlyBucket<GardenerScores> {
+    bucket_read(storage, SCORES_KEY)
+}
+
+fn ranking_namespaces(board: &Leaderboard) -> Vec<&[u8]> {
+    match board {
+        Leaderboard::BonsaiCount => vec![RANKINGS_KEY, b"bonsai_count"],
+        Leaderboard::TotalSpent { asset } => vec![RANKINGS_KEY, b"total_spent", asset.as_bytes()],
+        Leaderboard::OldestTree => vec![RANKINGS_KEY, b"oldest_tree"],
+    }
+}
+
+/// return a writable leaderboard, keyed by score then address, of the gardener addresses
+pub fn ranking_store<'a>(
+    storage: &'a mut dyn Storage,
+    board: &Leaderboard,
+) -> Bucket<'a, CanonicalAddr> {
+    Bucket::multilevel(storage, &ranking_namespaces(board))
+}
+
+/// return a read-only leaderboard, keyed by score then address, of the gardener addresses
+pub fn ranking_store_read<'a>(
+    storage: &'a dyn Storage,
+    board: &Leaderboard,
+) -> ReadonlyBucket<'a, CanonicalAddr> {
+    ReadonlyBucket::multilevel(storage, &ranking_namespaces(board))
+}
+
+/// refresh the scores of a gardener after its garden changed, adding what it just spent,
+/// and move it in the leaderboards accordingly
+pub fn update_scores(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    spent: Option<&Price>,
+) -> StdResult<()> {
+    let gardener = gardeners_store_read(storage).load(address.as_slice())?;
+    let mut scores = scores_store_read(storage)
+        .may_load(address.as_slice())?
+        .unwrap_or_default();
+    for (board, key) in scores.ranking_keys(address) {
+        ranking_store(storage, &board).remove(&key);
+    }
+
+    scores.bonsai_count = gardener.bonsais.len() as u64;
+    scores.oldest_tree = gardener
+        .bonsais
+        .iter()
+        .map(|bonsai| bonsai.birth_date)
+        .min();
+    if let Some(price) = spent {
+        match scores.spent.iter_mut().find(|s| s.is_same_asset(price)) {
+            Some(total) => *total = total.with_amount(total.amount() + price.amount()),
+            None => scores.spent.push(price.clone()),
+        }
+    }
+
+    for (board, key) in scores.ranking_keys(address) {
+        ranking_store(storage, &board).save(&key, address)?;
+    }
+    scores_store(storage).save(address.as_slice(), &scores)
+}
+
 /// return a writable bonsais list
 pub fn bonsai_store(storage: &mut dyn Storage) -> Singleton<BonsaiList> {
     singleton(storage, BONSAI_KEY)
@@ -57,11 +1078,24 @@ pub fn bonsai_store_read(storage: &dyn Storage) -> ReadonlySingleton<BonsaiList>
     singleton_read(storage, BONSAI_KEY)
 }
 
+/// return the writable list of cw20 contracts accepted as payment
+pub fn cw20_whitelist_store(storage: &mut dyn Storage) -> Singleton<Vec<CanonicalAddr>> {
+    singleton(storage, CW20_WHITELIST_KEY)
+}
+
+/// return the read-only list of cw20 contracts accepted as payment
+pub fn cw20_whitelist_store_read(storage: &dyn Storage) -> ReadonlySingleton<Vec<CanonicalAddr>> {
+    singleton_read(storage, CW20_WHITELIST_KEY)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Gardener {
     pub name: String,
     pub address: CanonicalAddr,
     pub bonsais: Vec<Bonsai>,
+    // how many bonsais the gardener bought from the shop
+    #[serde(default)]
+    pub purchased: u64,
 }
 
 impl Gardener {
@@ -71,6 +1105,7 @@ impl Gardener {
             name,
             address,
             bonsais,
+            purchased: 0,
         }
     }
 }
@@ -84,3 +1119,305 @@ pub fn gardeners_store(storage: &mut dyn Storage) -> Bucket<Gardener> {
 pub fn gardeners_store_read(storage: &dyn Storage) -> ReadonlyBucket<Gardener> {
     bucket_read(storage, GARDENERS_KEY)
 }
+
+/// a gardener's offer to sell one of their bonsais to the recipient,
+/// completed when the recipient pays one of the asking prices
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleOffer {
+    pub seller: CanonicalAddr,
+    pub recipient: CanonicalAddr,
+    pub prices: Vec<Price>,
+}
+
+/// return a writable bucket of sale offers, keyed by bonsai id
+pub fn sale_offers_store(storage: &mut dyn Storage) -> Bucket<SaleOffer> {
+    bucket(storage, SALE_OFFERS_KEY)
+}
+
+/// return a read-only bucket of sale offers, keyed by bonsai id
+pub fn sale_offers_store_read(storage: &dyn Storage) -> ReadonlyBucket<SaleOffer> {
+    bucket_read(storage, SALE_OFFERS_KEY)
+}
+
+/// the place where a bonsai lives: still in the shop or inside a gardener's garden
+#[derive(Clone, Debug, PartialEq)]
+pub enum BonsaiOwner {
+    Shop,
+    Gardener(CanonicalAddr),
+}
+
+// in-memory copy of the bonsais held by an owner, written back only
+// once the whole transfer has been validated
+enum Holding {
+    Shop(BonsaiList),
+    Gardener(Gardener),
+}
+
+impl Holding {
+    fn load(storage: &dyn Storage, owner: &BonsaiOwner) -> Result<Holding, MyCustomError> {
+        match owner {
+            BonsaiOwner::Shop => Ok(Holding::Shop(bonsai_store_read(storage).load()?)),
+            BonsaiOwner::Gardener(address) => gardeners_store_read(storage)
+                .may_load(address.as_slice())?
+                .map(Holding::Gardener)
+                .ok_or(MyCustomError::GardenerNotFound {}),
+        }
+    }
+
+    fn bonsais(&mut self) -> &mut Vec<Bonsai> {
+        match self {
+            Holding::Shop(bonsai_list) => &mut bonsai_list.bonsais,
+            Holding::Gardener(gardener) => &mut gardener.bonsais,
+        }
+    }
+
+    fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        match self {
+            Holding::Shop(bonsai_list) => bonsai_store(storage).save(bonsai_list),
+            Holding::Gardener(gardener) => {
+                gardeners_store(storage).save(gardener.address.as_slice(), gardener)
+            }
+        }
+    }
+}
+
+/// move the bonsai with the given id from an owner to another one.
+/// Both sides are checked before anything is saved, so the bonsai
+/// always ends up with exactly one owner
+pub fn transfer_bonsai(
+    storage: &mut dyn Storage,
+    from: &BonsaiOwner,
+    to: &BonsaiOwner,
+    bonsai_id: u64,
+) -> Result<Bonsai, MyCustomError> {
+    if from == to {
+        return Err(MyCustomError::AlreadyOwned { b_id: bonsai_id });
+    }
+
+    let mut source = Holding::load(storage, from)?;
+    let mut destination = Holding::load(storage, to)?;
+
+    let position = source
+        .bonsais()
+        .iter()
+        .position(|bonsai| bonsai.id == bonsai_id)
+        .ok_or(match from {
+            BonsaiOwner::Shop => MyCustomError::BonsaiNotFound { b_id: bonsai_id },
+            BonsaiOwner::Gardener(_) => MyCustomError::NotBonsaiOwner { b_id: bonsai_id },
+        })?;
+
+    if destination
+        .bonsais()
+        .iter()
+        .any(|bonsai| bonsai.id == bonsai_id)
+    {
+        return Err(MyCustomError::AlreadyOwned { b_id: bonsai_id });
+    }
+
+    let bonsai = source.bonsais().remove(position);
+    destination.bonsais().push(bonsai.clone());
+
+    source.save(storage)?;
+    destination.save(storage)?;
+
+    let owner = match to {
+        BonsaiOwner::Shop => None,
+        BonsaiOwner::Gardener(address) => Some(address),
+    };
+    index_bonsai(storage, &bonsai, owner)?;
+
+    Ok(bonsai)
+}
+
+/// a grown bonsai as found by the search, with where it is
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SearchEntry {
+    pub bonsai: Bonsai,
+    // none when the bonsai is in the shop
+    pub owner: Option<CanonicalAddr>,
+    // in the shop or offered by its owner
+    pub for_sale: bool,
+    // the asking price of the offer or the shop prices, none when not for sale
+    pub prices: Vec<Price>,
+}
+
+/// the secondary indexes of the search, each one keyed by bonsai id
+/// after the value it sorts by, if any. The sorted ones are also split
+/// by for sale status, so that filter narrows the range as well
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchIndex {
+    BirthDate,
+    Price(String),
+    BirthDateForSale(bool),
+    PriceForSale(bool, String),
+    Shop,
+    Owner(CanonicalAddr),
+    Species(Species),
+    Rarity(Rarity),
+}
+
+impl SearchIndex {
+    fn namespaces(&self) -> Vec<Vec<u8>> {
+        let name = |name: &str| vec![SEARCH_INDEX_KEY.to_vec(), name.as_bytes().to_vec()];
+        match self {
+            SearchIndex::BirthDate => name("birth_date"),
+            SearchIndex::Price(asset) => [name("price"), vec![asset.as_bytes().to_vec()]].concat(),
+            SearchIndex::BirthDateForSale(for_sale) => {
+                name(&format!("birth_date_for_sale_{}", for_sale))
+            }
+            SearchIndex::PriceForSale(for_sale, asset) => [
+                name(&format!("price_for_sale_{}", for_sale)),
+                vec![asset.as_bytes().to_vec()],
+            ]
+            .concat(),
+            SearchIndex::Shop => name("shop"),
+            SearchIndex::Owner(address) => [name("owner"), vec![address.to_vec()]].concat(),
+            SearchIndex::Species(species) => name(&format!("species_{:?}", species)),
+            SearchIndex::Rarity(rarity) => name(&format!("rarity_{:?}", rarity)),
+        }
+    }
+}
+
+impl SearchEntry {
+    /// the key ordering the bonsai by birth date, or by its list price in the given asset
+    pub fn sort_key(&self, asset: Option<&str>) -> Option<Vec<u8>> {
+        let score = match asset {
+            None => self.bonsai.birth_date.to_be_bytes().to_vec(),
+            Some(asset) => self
+                .prices
+                .iter()
+                .find(|price| price.asset() == asset)?
+                .amount()
+                .u128()
+                .to_be_bytes()
+                .to_vec(),
+        };
+        Some([score, self.bonsai.id.to_be_bytes().to_vec()].concat())
+    }
+
+    /// the indexes the bonsai appears in, with its key in each of them
+    fn index_keys(&self) -> Vec<(SearchIndex, Vec<u8>)> {
+        let id = self.bonsai.id.to_be_bytes().to_vec();
+        let date_key = self.sort_key(None).unwrap_or_default();
+        let mut keys = vec![
+            (SearchIndex::BirthDate, date_key.clone()),
+            (SearchIndex::BirthDateForSale(self.for_sale), date_key),
+        ];
+        for price in self.prices.iter() {
+            let asset = price.asset().to_string();
+            let key = self.sort_key(Some(&asset)).unwrap_or_default();
+            keys.push((SearchIndex::Price(asset.clone()), key.clone()));
+            keys.push((SearchIndex::PriceForSale(self.for_sale, asset), key));
+        }
+        match &self.owner {
+            Some(address) => keys.push((SearchIndex::Owner(address.clone()), id.clone())),
+            None => keys.push((SearchIndex::Shop, id.clone())),
+        }
+        if let Some(traits) = self.bonsai.traits {
+            keys.push((SearchIndex::Species(traits.species), id.clone()));
+            keys.push((SearchIndex::Rarity(traits.rarity), id));
+        }
+        keys
+    }
+}
+
+/// return the writable search entries, keyed by bonsai id
+pub fn search_entries_store(storage: &mut dyn Storage) -> Bucket<SearchEntry> {
+    bucket(storage, SEARCH_ENTRIES_KEY)
+}
+
+/// return the read-only search entries, keyed by bonsai id
+pub fn search_entries_store_read(storage: &dyn Storage) -> ReadonlyBucket<SearchEntry> {
+    bucket_read(storage, SEARCH_ENTRIES_KEY)
+}
+
+/// return a writable search index of bonsai ids
+pub fn search_index_store<'a>(
+    storage: &'a mut dyn Storage,
+    index: &SearchIndex,
+) -> Bucket<'a, u64> {
+    let namespaces = index.namespaces();
+    let namespaces: Vec<&[u8]> = namespaces.iter().map(Vec::as_slice).collect();
+    Bucket::multilevel(storage, &namespaces)
+}
+
+/// return a read-only search index of bonsai ids
+pub fn search_index_store_read<'a>(
+    storage: &'a dyn Storage,
+    index: &SearchIndex,
+) -> ReadonlyBucket<'a, u64> {
+    let namespaces = index.namespaces();
+    let namespaces: Vec<&[u8]> = namespaces.iter().map(Vec::as_slice).collect();
+    ReadonlyBucket::multilevel(storage, &namespaces)
+}
+
+/// put a bonsai held by the owner, or the shop, in the search indexes,
+/// replacing what they held about it
+pub fn index_bonsai(
+    storage: &mut dyn Storage,
+    bonsai: &Bonsai,
+    owner: Option<&CanonicalAddr>,
+) -> StdResult<()> {
+    unindex_bonsai(storage, bonsai.id)?;
+    let offer = sale_offers_store_read(storage).may_load(&bonsai.id.to_be_bytes())?;
+    let for_sale = owner.is_none() || offer.is_some();
+    // an owned bonsai not offered has no price to search it by
+    let prices = match (offer, owner) {
+        (Some(offer), _) => offer.prices,
+        (None, None) => bonsai.prices.clone(),
+        (None, Some(_)) => vec![],
+    };
+    let entry = SearchEntry {
+        bonsai: bonsai.clone(),
+        owner: owner.cloned(),
+        for_sale,
+        prices,
+    };
+    for (index, key) in entry.index_keys() {
+        search_index_store(storage, &index).save(&key, &bonsai.id)?;
+    }
+    search_entries_store(storage).save(&bonsai.id.to_be_bytes(), &entry)
+}
+
+/// index a bonsai again where it is, after an offer for it was made or removed
+pub fn reindex_bonsai(storage: &mut dyn Storage, id: u64) -> StdResult<()> {
+    match search_entries_store_read(storage).may_load(&id.to_be_bytes())? {
+        Some(entry) => index_bonsai(storage, &entry.bonsai, entry.owner.as_ref()),
+        None => Ok(()),
+    }
+}
+
+/// take a bonsai out of the search indexes
+pub fn unindex_bonsai(storage: &mut dyn Storage, id: u64) -> StdResult<()> {
+    if let Some(entry) = search_entries_store_read(storage).may_load(&id.to_be_bytes())? {
+        for (index, key) in entry.index_keys() {
+            search_index_store(storage, &index).remove(&key);
+        }
+        search_entries_store(storage).remove(&id.to_be_bytes());
+    }
+    Ok(())
+}
+
+/// return the ids of the bonsais held by more than one owner (shop included)
+#[cfg(any(test, feature = "invariants"))]
+pub fn duplicated_bonsais(storage: &dyn Storage) -> StdResult<Vec<u64>> {
+    let mut owners: std::collections::BTreeMap<u64, u32> = Default::default();
+
+    let shop = bonsai_store_read(storage).load()?;
+    for bonsai in shop.bonsais {
+        *owners.entry(bonsai.id).or_insert(0) += 1;
+    }
+
+    for item in gardeners_store_read(storage).range(None, None, Order::Ascending) {
+        let (_, gardener) = item?;
+        for bonsai in gardener.bonsais {
+            *owners.entry(bonsai.id).or_insert(0) += 1;
+        }
+    }
+
+    Ok(owners
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(id, _)| id)
+        .collect())
+}
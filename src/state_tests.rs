@@ -1,22 +1,229 @@
-use crate::state::{Bonsai, Gardener};
-use cosmwasm_std::testing::MockApi;
-use cosmwasm_std::{coin, Api, HumanAddr};
+use crate::errors::MyCustomError;
+use crate::state::{
+    bonsai_store, duplicated_bonsais, gardeners_store, gardeners_store_read, mul_div,
+    transfer_bonsai, Bonsai, BonsaiList, BonsaiOwner, Gardener, Presale, Price, PricingMode, Prng,
+    Rarity, Species, Stats, Traits, MAX_STEP_BPS,
+};
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{coin, Api, HumanAddr, Uint128};
+use cw20::Cw20CoinHuman;
+use sha2::{Digest, Sha256};
 
 #[test]
 fn new_bonsai() {
     let mut exp_bonsai = Bonsai {
         id: 1,
         birth_date: 100,
-        price: coin(145, "testCoin"),
+        prices: vec![coin(145, "testCoin").into()],
+        royalty: None,
+        traits: None,
     };
 
-    let cur_bonsai = Bonsai::new(1, 100, exp_bonsai.price.clone());
+    let cur_bonsai = Bonsai::new(1, 100, exp_bonsai.prices.clone());
 
     exp_bonsai.id = cur_bonsai.id.clone();
 
     assert_eq!(exp_bonsai, cur_bonsai)
 }
 
+#[test]
+fn bonsai_matching_price() {
+    let token = Price::Cw20(Cw20CoinHuman {
+        address: HumanAddr::from("token"),
+        amount: Uint128(20),
+    });
+    let bonsai = Bonsai::new(
+        1,
+        100,
+        vec![
+            coin(10, "ucosm").into(),
+            coin(5, "ustake").into(),
+            token.clone(),
+        ],
+    );
+
+    assert_eq!(None, bonsai.matching_price(&[]));
+    assert_eq!(
+        None,
+        bonsai.matching_price(&[coin(9, "ucosm").into(), coin(10, "uatom").into()])
+    );
+    assert_eq!(
+        Some(&Price::from(coin(5, "ustake"))),
+        bonsai.matching_price(&[coin(9, "ucosm").into(), coin(7, "ustake").into()])
+    );
+
+    // a native coin named like the token doesn't pay for the cw20 price
+    assert_eq!(None, bonsai.matching_price(&[coin(20, "token").into()]));
+    assert_eq!(
+        Some(&token),
+        bonsai.matching_price(std::slice::from_ref(&token))
+    );
+}
+
+#[test]
+fn pricing_mode_current_amount() {
+    let base = Uint128(1000);
+
+    assert_eq!(
+        base,
+        PricingMode::Fixed {}.current_amount(base, 5, 50).unwrap()
+    );
+
+    let linear = PricingMode::LinearCurve { step_bps: 500 };
+    assert_eq!(base, linear.current_amount(base, 0, 0).unwrap());
+    assert_eq!(Uint128(1150), linear.current_amount(base, 3, 0).unwrap());
+
+    let exponential = PricingMode::ExponentialCurve { step_bps: 1_000 };
+    assert_eq!(
+        Uint128(1100),
+        exponential.current_amount(base, 1, 0).unwrap()
+    );
+    assert_eq!(
+        Uint128(1210),
+        exponential.current_amount(base, 2, 0).unwrap()
+    );
+
+    let auction = PricingMode::DutchAuction {
+        decay_bps: 200,
+        floor_bps: 2_000,
+    };
+    assert_eq!(Uint128(900), auction.current_amount(base, 0, 5).unwrap());
+    assert_eq!(
+        Uint128(200),
+        auction.current_amount(base, 0, 1_000).unwrap()
+    );
+}
+
+#[test]
+fn pricing_curves_report_overflow() {
+    let base = Uint128(1_000_000);
+
+    // computed in a few steps, even after many sales
+    let exponential = PricingMode::ExponentialCurve { step_bps: 1_000 };
+    let price = exponential.current_amount(base, 100, 0).unwrap();
+    assert_eq!(13_780_612_339u128, price.u128());
+    match exponential.current_amount(base, 800, 0) {
+        Err(MyCustomError::PriceOverflow {}) => {}
+        res => panic!("expected PriceOverflow error, got {:?}", res),
+    }
+
+    let linear = PricingMode::LinearCurve {
+        step_bps: MAX_STEP_BPS,
+    };
+    let huge = Uint128(u128::MAX / 2);
+    assert!(linear.current_amount(huge, 1, 0).is_ok());
+    assert!(linear.current_amount(huge, 2, 0).is_err());
+
+    // the decay can't take the price below the floor
+    let auction = PricingMode::DutchAuction {
+        decay_bps: u64::MAX,
+        floor_bps: 1_000,
+    };
+    assert_eq!(
+        Uint128(100_000),
+        auction.current_amount(base, 0, u64::MAX).unwrap()
+    );
+}
+
+#[test]
+fn mul_div_uses_the_full_product() {
+    assert_eq!(Some(6), mul_div(4, 3, 2));
+    assert_eq!(None, mul_div(4, 3, 0));
+    assert_eq!(Some(u128::MAX), mul_div(u128::MAX, u128::MAX, u128::MAX));
+    assert_eq!(Some(u128::MAX / 3), mul_div(u128::MAX, 2, 6));
+    assert_eq!(None, mul_div(u128::MAX, 2, 1));
+}
+
+#[test]
+fn presale_verify_merkle_proof() {
+    let leo = HumanAddr::from("leo");
+    let ricky = HumanAddr::from("ricky");
+    let leo_leaf: [u8; 32] = Sha256::digest(b"leo").into();
+    let ricky_leaf: [u8; 32] = Sha256::digest(b"ricky").into();
+    let (first, second) = if leo_leaf <= ricky_leaf {
+        (leo_leaf, ricky_leaf)
+    } else {
+        (ricky_leaf, leo_leaf)
+    };
+    let root = Sha256::new().chain(first).chain(second).finalize();
+
+    let presale = Presale {
+        public_sale_height: 200,
+        merkle_root: Some(hex::encode(root)),
+    };
+    assert!(presale.verify(&leo, &[hex::encode(ricky_leaf)]).unwrap());
+    assert!(presale.verify(&ricky, &[hex::encode(leo_leaf)]).unwrap());
+    assert!(!presale.verify(&leo, &[hex::encode(leo_leaf)]).unwrap());
+    assert!(!presale
+        .verify(&HumanAddr::from("bob"), &[hex::encode(ricky_leaf)])
+        .unwrap());
+    assert!(presale.verify(&leo, &["not hex".to_string()]).is_err());
+
+    assert!(presale.is_active(199));
+    assert!(!presale.is_active(200));
+}
+
+#[test]
+fn prng_draws_deterministic_traits() {
+    let mut prng = Prng::new(&[b"seed", &1u64.to_be_bytes()]);
+    let mut same = Prng::new(&[b"seed", &1u64.to_be_bytes()]);
+    let mut other = Prng::new(&[b"seed", &2u64.to_be_bytes()]);
+    let first = prng.next_u64();
+    assert_eq!(first, same.next_u64());
+    assert_ne!(first, other.next_u64());
+    assert_ne!(first, prng.next_u64());
+
+    // the common bonsais are the most frequent and every rarity is drawn
+    let mut counts = [0u32; 4];
+    for id in 0..1000u64 {
+        let traits = Traits::random(&mut Prng::new(&[&id.to_be_bytes()]));
+        counts[traits.rarity as usize] += 1;
+    }
+    assert!(counts.iter().all(|count| *count > 0));
+    assert!(counts[Rarity::Common as usize] > counts[Rarity::Uncommon as usize]);
+    assert!(counts[Rarity::Uncommon as usize] > counts[Rarity::Legendary as usize]);
+}
+
+#[test]
+fn cross_traits() {
+    let pine = Traits {
+        species: Species::Pine,
+        rarity: Rarity::Rare,
+    };
+    let elm = Traits {
+        species: Species::Elm,
+        rarity: Rarity::Common,
+    };
+    for seed in 0..50u64 {
+        let mut prng = Prng::new(&[&seed.to_be_bytes()]);
+        let child = Traits::cross(&pine, &elm, &mut prng);
+        assert!(child.species == Species::Pine || child.species == Species::Elm);
+        assert_eq!(Rarity::Common, child.rarity);
+
+        let child = Traits::cross(&pine, &pine, &mut prng);
+        assert!(child.rarity == Rarity::Rare || child.rarity == Rarity::Legendary);
+    }
+    assert_eq!(Rarity::Legendary, Rarity::Legendary.next());
+}
+
+#[test]
+fn stats_record_sales_per_asset() {
+    let token = Price::Cw20(Cw20CoinHuman {
+        address: HumanAddr::from("token"),
+        amount: Uint128(30),
+    });
+    let mut stats = Stats::default();
+    stats.record_sale(&coin(10, "ucosm").into());
+    stats.record_sale(&token);
+    stats.record_sale(&coin(4, "ucosm").into());
+    stats.record_sale(&coin(7, "ucosm").into());
+
+    assert_eq!(4, stats.sales);
+    assert_eq!(vec![coin(21, "ucosm").into(), token.clone()], stats.volume);
+    assert_eq!(vec![coin(4, "ucosm").into(), token], stats.floor);
+    assert_eq!(Some(coin(7, "ucosm").into()), stats.last_sale);
+}
+
 #[test]
 fn new_gardener() {
     let api = MockApi::default();
@@ -25,6 +232,7 @@ fn new_gardener() {
         name: "leo".to_string(),
         address: api.canonical_address(&HumanAddr::from("addr")).unwrap(),
         bonsais: vec![],
+        purchased: 0,
     };
 
     let cur_gardener = Gardener::new(
@@ -41,3 +249,78 @@ fn grow_bonsais() {
     //let bonsai_list = BonsaiList::grow_bonsais(20, 100, coin(20, "bonsai"), mock_dependencies(&[]););
     //assert_eq!(20, bonsai_list.bonsais.len())
 }
+
+#[test]
+fn transfer_bonsai_moves_ownership() {
+    let api = MockApi::default();
+    let mut storage = MockStorage::new();
+
+    let bonsai_list = BonsaiList::grow_bonsais(3, 100, vec![coin(20, "bonsai").into()], None);
+    bonsai_store(&mut storage).save(&bonsai_list).unwrap();
+
+    let addr = api.canonical_address(&HumanAddr::from("addr")).unwrap();
+    let gardener = Gardener::new("leo".to_string(), addr.clone(), vec![]);
+    gardeners_store(&mut storage)
+        .save(addr.as_slice(), &gardener)
+        .unwrap();
+
+    let owner = BonsaiOwner::Gardener(addr.clone());
+    let bonsai = transfer_bonsai(&mut storage, &BonsaiOwner::Shop, &owner, 1).unwrap();
+    assert_eq!(1, bonsai.id);
+
+    let gardener = gardeners_store_read(&storage)
+        .load(addr.as_slice())
+        .unwrap();
+    assert_eq!(vec![bonsai], gardener.bonsais);
+
+    // the bonsai is no longer in the shop
+    let res = transfer_bonsai(&mut storage, &BonsaiOwner::Shop, &owner, 1);
+    match res {
+        Err(MyCustomError::BonsaiNotFound { b_id }) => assert_eq!(1, b_id),
+        _ => panic!("expected BonsaiNotFound error, got {:?}", res),
+    }
+
+    assert!(duplicated_bonsais(&storage).unwrap().is_empty())
+}
+
+#[test]
+fn transfer_bonsai_to_unknown_gardener_keeps_owner() {
+    let api = MockApi::default();
+    let mut storage = MockStorage::new();
+
+    let bonsai_list = BonsaiList::grow_bonsais(3, 100, vec![coin(20, "bonsai").into()], None);
+    bonsai_store(&mut storage).save(&bonsai_list).unwrap();
+
+    let addr = api.canonical_address(&HumanAddr::from("addr")).unwrap();
+    let res = transfer_bonsai(
+        &mut storage,
+        &BonsaiOwner::Shop,
+        &BonsaiOwner::Gardener(addr),
+        1,
+    );
+    match res {
+        Err(MyCustomError::GardenerNotFound {}) => {}
+        _ => panic!("expected GardenerNotFound error, got {:?}", res),
+    }
+}
+
+#[test]
+fn duplicated_bonsais_detects_double_owners() {
+    let api = MockApi::default();
+    let mut storage = MockStorage::new();
+
+    let bonsai_list = BonsaiList::grow_bonsais(3, 100, vec![coin(20, "bonsai").into()], None);
+    bonsai_store(&mut storage).save(&bonsai_list).unwrap();
+
+    let addr = api.canonical_address(&HumanAddr::from("addr")).unwrap();
+    let gardener = Gardener::new(
+        "leo".to_string(),
+        addr.clone(),
+        vec![bonsai_list.bonsais[2].clone()],
+    );
+    gardeners_store(&mut storage)
+        .save(addr.as_slice(), &gardener)
+        .unwrap();
+
+    assert_eq!(vec![2], duplicated_bonsais(&storage).unwrap())
+}
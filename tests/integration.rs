@@ -27,7 +27,7 @@ use cosmwasm_vm::testing::{
 };
 use cosmwasm_vm::{Instance, Storage};
 use my_first_contract::msg::{AllGardenersResponse, HandleMsg, InitMsg, QueryMsg};
-use my_first_contract::state::{BonsaiList, Gardener, BONSAI_KEY};
+use my_first_contract::state::{BonsaiList, Gardener, PricingMode, BONSAI_KEY};
 use rand::seq::SliceRandom;
 
 const WASM: &[u8] =
@@ -50,8 +50,18 @@ fn setup_test(
     bonsai_number: u64,
 ) {
     let init_msg = InitMsg {
-        price: bonsai_price,
+        owner: None,
+        prices: vec![bonsai_price.into()],
         number: bonsai_number,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
     };
     let _res: InitResponse = init(deps, env.clone(), info, init_msg).unwrap();
 }
@@ -105,8 +115,18 @@ fn test_init() {
 
     // Init an empty contract
     let init_msg = InitMsg {
-        price: coin(20, BOND_DENOM),
+        owner: None,
+        prices: vec![coin(20, BOND_DENOM).into()],
         number: 20,
+        cw20_whitelist: vec![],
+        protocol_fee_bps: 0,
+        fee_collector: HumanAddr::from("collector"),
+        royalty_bps: 0,
+        pricing: PricingMode::Fixed {},
+        presale: None,
+        max_per_wallet: None,
+        max_initial_inventory: None,
+        commit_reveal: false,
     };
     let env = mock_env_height(100);
     let info = mock_info("sender", &coins(1000, BOND_DENOM));
@@ -136,7 +156,8 @@ fn test_become_gardener_works() {
     let mut exp_res = HandleResponse::default();
     exp_res.attributes = vec![
         attr("action", "become_gardener"),
-        attr("gardener_addr", &sender_addr),
+        attr("from", &sender_addr),
+        attr("height", 100),
     ];
 
     let res = become_gardener("leo".to_string(), info.clone(), env.clone(), &mut deps);
@@ -169,8 +190,13 @@ fn test_buy_bonsai_works() {
     let mut exp_res = HandleResponse::default();
     exp_res.attributes = vec![
         attr("action", "buy_bonsai"),
-        attr("buyer", &info.sender),
-        attr("amount", bonsai_price.amount),
+        attr("bonsai_id", bonsai_id),
+        attr("from", &env.contract.address),
+        attr("to", &info.sender),
+        attr("price", bonsai_price.amount),
+        attr("denom", BOND_DENOM),
+        attr("height", 100),
+        attr("protocol_fee", 0u128),
     ];
     let res = buy_bonsai(bonsai_id, info.clone(), env.clone(), &mut deps);
     assert_eq!(exp_res, res);
@@ -183,11 +209,11 @@ fn test_buy_bonsai_works() {
 
 #[test]
 fn test_sell_bonsai_works() {
-    let mut deps = mock_instance(WASM, &[]);
-
     let sender_addr = HumanAddr::from("addr0001");
     let buyer_addr = HumanAddr::from("addr0002");
-    let bonsai_price = coin(0, BOND_DENOM);
+    let mut deps =
+        mock_instance_with_balances(WASM, &[(&buyer_addr.clone(), &coins(1000, BOND_DENOM))]);
+    let bonsai_price = coin(10, BOND_DENOM);
     let env = mock_env_height(100);
     let info = mock_info(sender_addr.clone(), &coins(1000, BOND_DENOM));
 
@@ -216,6 +242,7 @@ fn test_sell_bonsai_works() {
     let msg = HandleMsg::SellBonsai {
         recipient: buyer_addr.clone(),
         b_id: bonsai_id,
+        prices: vec![bonsai_price.clone().into()],
     };
     let res: HandleResponse = handle(
         &mut deps,
@@ -228,14 +255,29 @@ fn test_sell_bonsai_works() {
     let mut exp_res = HandleResponse::default();
     exp_res.attributes = vec![
         attr("action", "sell_bonsai"),
+        attr("bonsai_id", bonsai_id),
         attr("from", info.sender.clone()),
         attr("to", buyer_addr.clone()),
+        attr("price", bonsai_price.amount),
+        attr("denom", BOND_DENOM),
+        attr("height", env.block.height),
     ];
 
     assert_eq!(exp_res, res);
 
+    // the buyer completes the sale paying for the bonsai
+    let _res = buy_bonsai(
+        bonsai_id,
+        mock_info(buyer_addr.clone(), &coins(1000, BOND_DENOM)),
+        env.clone(),
+        &mut deps,
+    );
+
     let gardener: Gardener = query_gardener(&mut deps, env.clone(), info.sender.clone());
-    assert_eq!(0, gardener.bonsais.len())
+    assert_eq!(0, gardener.bonsais.len());
+
+    let buyer: Gardener = query_gardener(&mut deps, env.clone(), buyer_addr.clone());
+    assert_eq!(bonsai_id, buyer.bonsais[0].id)
 }
 
 #[test]
@@ -255,15 +297,19 @@ fn test_cut_bonsai_works() {
 
     let _res = buy_bonsai(bonsai_id, info.clone(), env.clone(), &mut deps);
 
-    let msg = HandleMsg::CutBonsai { b_id: bonsai_id };
+    let msg = HandleMsg::CutBonsai {
+        b_id: bonsai_id,
+        propagate: false,
+    };
 
     let res: HandleResponse = handle(&mut deps, env.clone(), info.clone(), msg).unwrap();
 
     let mut exp_res = HandleResponse::default();
     exp_res.attributes = vec![
         attr("action", "cut_bonsai"),
-        attr("owner", info.sender.clone()),
         attr("bonsai_id", bonsai_id),
+        attr("from", info.sender.clone()),
+        attr("height", 100),
     ];
 
     assert_eq!(exp_res, res);